@@ -0,0 +1,519 @@
+use std::ascii::AsciiExt;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Equals,
+    Tab,
+    Space,
+    Hunspell,
+    Text
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "equals" => Some(Format::Equals),
+            "tab" => Some(Format::Tab),
+            "space" => Some(Format::Space),
+            "hunspell" => Some(Format::Hunspell),
+            "text" => Some(Format::Text),
+            _ => None
+        }
+    }
+
+    // Hunspell files are recognized by their extension, frequency lists by their first entry.
+    pub fn detect(source: &Path) -> Format {
+        if source.extension().map_or(false, |extension| extension == "dic" || extension == "aff") {
+            return Format::Hunspell;
+        }
+
+        let lines = File::open(source).map(|file| BufReader::new(file).lines().take(10).collect::<Vec<_>>());
+        let line = lines.unwrap_or_else(|_| Vec::new()).into_iter().filter_map(|line| line.ok()).find(|line| {
+            !line.trim().is_empty() && !line.starts_with('#')
+        });
+        match line {
+            Some(ref line) if split_entry(line, Format::Equals).is_some() => Format::Equals,
+            Some(ref line) if split_entry(line, Format::Tab).is_some() => Format::Tab,
+            Some(ref line) if line.split_whitespace().count() == 2 && split_entry(line, Format::Space).is_some() => {
+                Format::Space
+            }
+            _ => Format::Text
+        }
+    }
+}
+
+// Returns the problems with single lines, which are skipped, as "file:line: message".
+pub fn read_input(source: &Path, format: Format, counts: &mut BTreeMap<String, u64>) -> io::Result<Vec<String>> {
+    let mut problems = Vec::new();
+    let result = match format {
+        Format::Hunspell => read_hunspell(source, counts, &mut problems),
+        Format::Text => read_text(source, counts, &mut problems),
+        _ => read_frequency_list(source, format, counts, &mut problems)
+    };
+    result.map(|_| problems)
+}
+
+pub fn for_each_line<F: FnMut(usize, &str) -> Result<(), String>>(source: &Path, latin1: bool, problems: &mut Vec<String>,
+                                                                  mut f: F) -> io::Result<()> {
+    let mut reader = BufReader::new(File::open(source)?);
+    let mut bytes = Vec::new();
+    let mut number = 0;
+    while reader.read_until(b'\n', &mut bytes)? > 0 {
+        number += 1;
+        {
+            let bytes = bytes.iter().cloned().take_while(|&byte| byte != b'\n' && byte != b'\r').collect::<Vec<_>>();
+            let result = if latin1 {
+                f(number, &bytes.iter().map(|&byte| byte as char).collect::<String>())
+            } else {
+                match String::from_utf8(bytes) {
+                    Ok(line) => f(number, &line),
+                    Err(_) => Err(t!("The line is not valid UTF-8").to_string())
+                }
+            };
+            if let Err(message) = result {
+                problems.push(format!("{}:{}: {}", source.display(), number, message));
+            }
+        }
+        bytes.clear();
+    }
+    Ok(())
+}
+
+fn split_entry(line: &str, format: Format) -> Option<(&str, u64)> {
+    let mut parts = match format {
+        Format::Equals => line.trim().rsplitn(2, '=').collect::<Vec<_>>(),
+        Format::Tab => line.trim().rsplitn(2, '\t').collect::<Vec<_>>(),
+        _ => line.trim().rsplitn(2, char::is_whitespace).collect::<Vec<_>>()
+    };
+    if parts.len() != 2 || parts[1].trim().is_empty() {
+        return None;
+    }
+    let word = parts.pop().unwrap().trim();
+    parts[0].trim().parse().ok().map(|frequency| (word, frequency))
+}
+
+fn read_frequency_list(source: &Path, format: Format, counts: &mut BTreeMap<String, u64>,
+                       problems: &mut Vec<String>) -> io::Result<()> {
+    for_each_line(source, false, problems, |_, line| {
+        if line.trim().is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
+        match split_entry(line, format) {
+            Some((word, frequency)) => {
+                *counts.entry(word.to_string()).or_insert(0) += frequency;
+                Ok(())
+            }
+            None => Err(t!("Expected a word followed by a frequency").to_string())
+        }
+    })
+}
+
+fn read_text(source: &Path, counts: &mut BTreeMap<String, u64>, problems: &mut Vec<String>) -> io::Result<()> {
+    let mut sentence_start = true;
+    for_each_line(source, false, problems, |_, line| {
+        let mut word = String::new();
+        for c in line.chars().chain(Some(' ')) {
+            if c.is_alphanumeric() || ((c == '\'' || c == '-') && !word.is_empty()) {
+                word.push(c);
+                continue;
+            }
+
+            {
+                let token = word.trim_right_matches(|c: char| c == '\'' || c == '-');
+                if token.chars().any(char::is_alphabetic) {
+                    *counts.entry(text_word(token, sentence_start)).or_insert(0) += 1;
+                    sentence_start = false;
+                }
+            }
+            if c == '.' || c == '!' || c == '?' {
+                sentence_start = true;
+            }
+            word.clear();
+        }
+        Ok(())
+    })
+}
+
+// A capital at the start of a sentence doesn't say anything about the word itself.
+fn text_word(word: &str, sentence_start: bool) -> String {
+    let mut chars = word.chars();
+    let initial_only = chars.next().map_or(false, char::is_uppercase) && !chars.any(char::is_uppercase);
+    if sentence_start && initial_only {
+        word.to_lowercase()
+    } else {
+        word.to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum FlagType {
+    Char,
+    Long,
+    Number
+}
+
+#[derive(Debug)]
+enum Condition {
+    Any,
+    Char(char),
+    Set(bool, Vec<char>)
+}
+
+impl Condition {
+    fn parse(pattern: &str) -> Option<Vec<Condition>> {
+        if pattern == "." {
+            return Some(Vec::new());
+        }
+
+        let mut conditions = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            conditions.push(match c {
+                '.' => Condition::Any,
+                '[' => {
+                    let set = chars.by_ref().take_while(|&c| c != ']').collect::<Vec<_>>();
+                    if set.is_empty() {
+                        return None;
+                    }
+                    if set[0] == '^' {
+                        Condition::Set(true, set[1..].to_vec())
+                    } else {
+                        Condition::Set(false, set)
+                    }
+                }
+                ']' => return None,
+                c => Condition::Char(c)
+            });
+        }
+        Some(conditions)
+    }
+
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            Condition::Any => true,
+            Condition::Char(expected) => c == expected,
+            Condition::Set(negated, ref set) => set.contains(&c) != negated
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Affix {
+    prefix: bool,
+    flag: String,
+    strip: String,
+    add: String,
+    conditions: Vec<Condition>
+}
+
+impl Affix {
+    fn apply(&self, word: &str) -> Option<String> {
+        let chars = word.chars().collect::<Vec<_>>();
+        let strip = self.strip.chars().count();
+        if chars.len() < self.conditions.len() || chars.len() <= strip {
+            return None;
+        }
+
+        if self.prefix {
+            let matches = chars.iter().zip(&self.conditions).all(|(&c, condition)| condition.matches(c));
+            if matches && word.starts_with(&self.strip[..]) {
+                return Some(format!("{}{}", self.add, &word[self.strip.len()..]));
+            }
+        } else {
+            let end = &chars[chars.len() - self.conditions.len()..];
+            let matches = end.iter().zip(&self.conditions).all(|(&c, condition)| condition.matches(c));
+            if matches && word.ends_with(&self.strip[..]) {
+                return Some(format!("{}{}", &word[..word.len() - self.strip.len()], self.add));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug)]
+struct Affixes {
+    latin1: bool,
+    flag_type: FlagType,
+    aliases: Vec<Vec<String>>,
+    cross_product: HashMap<(bool, String), bool>,
+    rules: Vec<Affix>
+}
+
+impl Affixes {
+    fn new() -> Affixes {
+        Affixes {
+            latin1: false,
+            flag_type: FlagType::Char,
+            aliases: Vec::new(),
+            cross_product: HashMap::new(),
+            rules: Vec::new()
+        }
+    }
+
+    fn from_file(source: &Path, problems: &mut Vec<String>) -> io::Result<Affixes> {
+        let mut affixes = Affixes::new();
+        affixes.latin1 = is_latin1(source)?;
+        let mut alias_count = None;
+        let latin1 = affixes.latin1;
+        for_each_line(source, latin1, problems, |_, line| {
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            match parts.first() {
+                Some(&"FLAG") if parts.len() > 1 => {
+                    affixes.flag_type = match parts[1] {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Number,
+                        _ => FlagType::Char
+                    };
+                }
+                Some(&"AF") if parts.len() > 1 && alias_count.is_none() => {
+                    alias_count = parts[1].parse::<usize>().ok();
+                }
+                Some(&"AF") if parts.len() > 1 => {
+                    let flags = affixes.parse_flags(parts[1]);
+                    affixes.aliases.push(flags);
+                }
+                Some(&kind) if kind == "PFX" || kind == "SFX" => {
+                    let prefix = kind == "PFX";
+                    if parts.len() == 4 && (parts[2] == "Y" || parts[2] == "N") && parts[3].parse::<usize>().is_ok() {
+                        affixes.cross_product.insert((prefix, parts[1].to_string()), parts[2] == "Y");
+                    } else if parts.len() >= 5 {
+                        match Condition::parse(parts[4]) {
+                            Some(conditions) => {
+                                let add = parts[3].split('/').next().unwrap_or("");
+                                affixes.rules.push(Affix {
+                                    prefix: prefix,
+                                    flag: parts[1].to_string(),
+                                    strip: if parts[2] == "0" { String::new() } else { parts[2].to_string() },
+                                    add: if add == "0" { String::new() } else { add.to_string() },
+                                    conditions: conditions
+                                });
+                            }
+                            None => return Err(format!(t!("Invalid affix condition: {}"), parts[4]))
+                        }
+                    } else {
+                        return Err(t!("Invalid affix rule").to_string());
+                    }
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(affixes)
+    }
+
+    fn parse_flags(&self, flags: &str) -> Vec<String> {
+        match self.flag_type {
+            FlagType::Char => flags.chars().map(|c| c.to_string()).collect(),
+            FlagType::Long => {
+                let chars = flags.chars().collect::<Vec<_>>();
+                chars.chunks(2).map(|flag| flag.iter().cloned().collect()).collect()
+            }
+            FlagType::Number => flags.split(',').map(|flag| flag.trim().to_string()).collect()
+        }
+    }
+
+    fn flags(&self, flags: &str) -> Vec<String> {
+        if !self.aliases.is_empty() && !flags.is_empty() && flags.chars().all(|c| c.is_digit(10)) {
+            let index = flags.parse::<usize>().unwrap_or(0);
+            if index > 0 && index <= self.aliases.len() {
+                return self.aliases[index - 1].clone();
+            }
+        }
+        self.parse_flags(flags)
+    }
+
+    fn cross_product(&self, affix: &Affix) -> bool {
+        *self.cross_product.get(&(affix.prefix, affix.flag.clone())).unwrap_or(&false)
+    }
+
+    fn expand(&self, stem: &str, flags: &[String]) -> Vec<String> {
+        let rules = self.rules.iter().filter(|affix| flags.contains(&affix.flag)).collect::<Vec<_>>();
+
+        let mut words = vec![stem.to_string()];
+        let mut suffixed = Vec::new();
+        for affix in rules.iter().filter(|affix| !affix.prefix) {
+            if let Some(word) = affix.apply(stem) {
+                if self.cross_product(affix) {
+                    suffixed.push(word.clone());
+                }
+                words.push(word);
+            }
+        }
+        for affix in rules.iter().filter(|affix| affix.prefix) {
+            words.extend(affix.apply(stem));
+            if self.cross_product(affix) {
+                words.extend(suffixed.iter().filter_map(|word| affix.apply(word)));
+            }
+        }
+        words
+    }
+}
+
+fn is_latin1(source: &Path) -> io::Result<bool> {
+    let mut bytes = Vec::new();
+    File::open(source)?.read_to_end(&mut bytes)?;
+    Ok(bytes.split(|&byte| byte == b'\n').any(|line| {
+        let line = String::from_utf8_lossy(line);
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        parts.len() > 1 && parts[0] == "SET" && parts[1].eq_ignore_ascii_case("ISO8859-1")
+    }))
+}
+
+// Hunspell word lists have no frequencies, so every stem and every form derived from it counts once.
+fn read_hunspell(source: &Path, counts: &mut BTreeMap<String, u64>, problems: &mut Vec<String>) -> io::Result<()> {
+    let dic = source.with_extension("dic");
+    let aff = source.with_extension("aff");
+    let affixes = if aff.exists() { Affixes::from_file(&aff, problems)? } else { Affixes::new() };
+
+    for_each_line(&dic, affixes.latin1, problems, |number, line| {
+        let entry = line.split('\t').next().unwrap_or("").trim();
+        if entry.is_empty() || (number == 1 && entry.parse::<usize>().is_ok()) {
+            return Ok(());
+        }
+
+        let entry = entry.split_whitespace().next().unwrap_or("");
+        let (stem, flags) = match entry.char_indices().find(|&(index, c)| c == '/' && !entry[..index].ends_with('\\')) {
+            Some((index, _)) => (&entry[..index], affixes.flags(&entry[index + 1..])),
+            None => (entry, Vec::new())
+        };
+        let stem = stem.replace("\\/", "/");
+        if stem.is_empty() {
+            return Err(t!("Expected a word").to_string());
+        }
+        for word in affixes.expand(&stem, &flags) {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_input, Format};
+
+    use tempdir::TempDir;
+
+    use std::collections::BTreeMap;
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+
+    fn fixture(temp_dir: &TempDir, name: &str, contents: &[u8]) -> PathBuf {
+        let path = temp_dir.path().join(name);
+        File::create(&path).and_then(|mut file| file.write_all(contents)).unwrap();
+        path
+    }
+
+    fn read(source: &Path, format: Format) -> (Vec<(String, u64)>, Vec<String>) {
+        let mut counts = BTreeMap::new();
+        let problems = read_input(source, format, &mut counts).unwrap();
+        (counts.into_iter().collect(), problems)
+    }
+
+    fn counts(entries: &[(&str, u64)]) -> Vec<(String, u64)> {
+        entries.iter().map(|&(word, count)| (word.to_string(), count)).collect()
+    }
+
+    fn lines(source: &Path, numbers: &[usize]) -> Vec<String> {
+        numbers.iter().map(|number| format!("{}:{}: ", source.display(), number)).collect()
+    }
+
+    fn problem_lines(problems: &[String]) -> Vec<String> {
+        problems.iter().map(|problem| problem[..problem.find(": ").unwrap() + 2].to_string()).collect()
+    }
+
+    #[test]
+    fn detection() {
+        let temp_dir = TempDir::new("commcomm-test").unwrap();
+        assert_eq!(Format::detect(&fixture(&temp_dir, "words.dic", b"1\nhi\n")), Format::Hunspell);
+        assert_eq!(Format::detect(&fixture(&temp_dir, "equals.txt", b"# counts\n\nhi=3\n")), Format::Equals);
+        assert_eq!(Format::detect(&fixture(&temp_dir, "tab.txt", b"hi there\t3\n")), Format::Tab);
+        assert_eq!(Format::detect(&fixture(&temp_dir, "space.txt", b"hi 3\n")), Format::Space);
+        assert_eq!(Format::detect(&fixture(&temp_dir, "text.txt", b"Hi there, 3 times.\n")), Format::Text);
+    }
+
+    #[test]
+    fn frequency_lists() {
+        let temp_dir = TempDir::new("commcomm-test").unwrap();
+
+        let source = fixture(&temp_dir, "equals.txt", b"# counts\nhi = 3\nhi=2\nice cream = 4\nbad line\n");
+        let (words, problems) = read(&source, Format::Equals);
+        assert_eq!(words, counts(&[("hi", 5), ("ice cream", 4)]));
+        assert_eq!(problem_lines(&problems), lines(&source, &[5]));
+
+        let source = fixture(&temp_dir, "tab.txt", b"hi there\t3\nhi\t2\nbad\n");
+        let (words, problems) = read(&source, Format::Tab);
+        assert_eq!(words, counts(&[("hi", 2), ("hi there", 3)]));
+        assert_eq!(problem_lines(&problems), lines(&source, &[3]));
+
+        let source = fixture(&temp_dir, "space.txt", b"hi 3\nthere  4\n\nhi x\n");
+        let (words, problems) = read(&source, Format::Space);
+        assert_eq!(words, counts(&[("hi", 3), ("there", 4)]));
+        assert_eq!(problem_lines(&problems), lines(&source, &[4]));
+    }
+
+    #[test]
+    fn text() {
+        let temp_dir = TempDir::new("commcomm-test").unwrap();
+        let source = fixture(&temp_dir, "text.txt",
+                             b"Hello there. Hello Anna!\n\xff\xfe\nMcDonald's is here-ish -- ok? The end");
+        let (words, problems) = read(&source, Format::Text);
+        assert_eq!(words, counts(&[("Anna", 1), ("McDonald's", 1), ("end", 1), ("hello", 2), ("here-ish", 1),
+                                   ("is", 1), ("ok", 1), ("the", 1), ("there", 1)]));
+        assert_eq!(problem_lines(&problems), lines(&source, &[2]));
+    }
+
+    #[test]
+    fn hunspell_affixes() {
+        let temp_dir = TempDir::new("commcomm-test").unwrap();
+        let aff = fixture(&temp_dir, "words.aff", b"SET UTF-8\n\
+                                                    PFX U Y 1\n\
+                                                    PFX U 0 un .\n\
+                                                    SFX S Y 1\n\
+                                                    SFX S 0 s .\n\
+                                                    SFX D N 1\n\
+                                                    SFX D 0 ed [^e]\n\
+                                                    PFX R N 1\n\
+                                                    PFX R 0 re .\n\
+                                                    SFX X Y 1\n\
+                                                    SFX X 0 x [\n");
+        let dic = fixture(&temp_dir, "words.dic", b"4\ndo/US\nlike/DR\nwalk/D\n/S\n");
+        let (words, problems) = read(&dic, Format::Hunspell);
+        assert_eq!(words, counts(&[("do", 1), ("dos", 1), ("like", 1), ("relike", 1), ("undo", 1), ("undos", 1),
+                                   ("walk", 1), ("walked", 1)]));
+        let mut expected = lines(&aff, &[11]);
+        expected.extend(lines(&dic, &[5]));
+        assert_eq!(problem_lines(&problems), expected);
+    }
+
+    #[test]
+    fn hunspell_flags() {
+        let temp_dir = TempDir::new("commcomm-test").unwrap();
+
+        // Long flags, aliases and Latin-1 text.
+        fixture(&temp_dir, "latin1.aff", b"SET ISO8859-1\n\
+                                           FLAG long\n\
+                                           AF 2\n\
+                                           AF AaBb\n\
+                                           AF Bb\n\
+                                           SFX Aa Y 1\n\
+                                           SFX Aa 0 s .\n\
+                                           PFX Bb Y 1\n\
+                                           PFX Bb 0 \xe9 .\n");
+        let dic = fixture(&temp_dir, "latin1.dic", b"2\ncaf\xe9/1\nt\xe9/2\n");
+        let (words, problems) = read(&dic, Format::Hunspell);
+        assert_eq!(words, counts(&[("café", 1), ("cafés", 1), ("té", 1), ("écafé", 1), ("écafés", 1), ("été", 1)]));
+        assert!(problems.is_empty());
+
+        // Numeric flags.
+        fixture(&temp_dir, "numbers.aff", b"FLAG num\nSFX 101 N 1\nSFX 101 0 en .\nPFX 7 N 1\nPFX 7 0 ge .\n");
+        let dic = fixture(&temp_dir, "numbers.dic", b"1\nlach/101,7\n");
+        let (words, problems) = read(&dic, Format::Hunspell);
+        assert_eq!(words, counts(&[("gelach", 1), ("lach", 1), ("lachen", 1)]));
+        assert!(problems.is_empty());
+    }
+}
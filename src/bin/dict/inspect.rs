@@ -0,0 +1,248 @@
+use commcomm::decoder::Dictionary;
+
+use clap::ArgMatches;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::fail;
+
+fn open(matches: &ArgMatches, name: &str) -> Dictionary {
+    let path = Path::new(matches.value_of(name).unwrap());
+    Dictionary::from_file(path).unwrap_or_else(|error| fail(&format!("{}: {}", path.display(), error)))
+}
+
+fn output(matches: &ArgMatches) -> Box<Write> {
+    match matches.value_of("OUTPUT") {
+        Some(path) => Box::new(BufWriter::new(File::create(path).unwrap_or_else(|error| fail(&error.to_string())))),
+        None => Box::new(io::stdout())
+    }
+}
+
+// Sums the frequencies of each spelling, so that dictionaries can be compared word by word.
+fn word_frequencies(dictionary: &Dictionary) -> BTreeMap<&str, u64> {
+    let mut frequencies = BTreeMap::new();
+    for (word, frequency) in dictionary.words() {
+        *frequencies.entry(word).or_insert(0) += frequency;
+    }
+    frequencies
+}
+
+fn write_lookup<W: Write>(dictionary: &Dictionary, word: &str, writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "{}: {}", t!("Key"), dictionary.key(word))?;
+    let entries = dictionary.lookup(word);
+    if entries.is_empty() {
+        writeln!(writer, "{}", t!("No entries found"))?;
+    }
+    for (word, frequency) in entries {
+        writeln!(writer, "{}\t{}", word, frequency)?;
+    }
+    Ok(())
+}
+
+fn write_prefix<W: Write>(dictionary: &Dictionary, prefix: &str, limit: usize, writer: &mut W) -> io::Result<()> {
+    for (word, frequency) in dictionary.completions(prefix).into_iter().take(limit) {
+        writeln!(writer, "{}\t{}", word, frequency)?;
+    }
+    Ok(())
+}
+
+fn write_stats<W: Write>(dictionary: &Dictionary, writer: &mut W) -> io::Result<()> {
+    let header = dictionary.header();
+    let unknown = t!("unknown");
+    writeln!(writer, "{}: {}", t!("Format version"), header.format_version)?;
+    writeln!(writer, "{}: {}", t!("Language"), header.language.as_ref().map_or(unknown, |language| &language[..]))?;
+    writeln!(writer, "{}: {} ({} {})", t!("Normalization"), header.normalization, t!("version"),
+             header.normalization_version)?;
+    writeln!(writer, "{}: {}", t!("Source"), header.source.as_ref().map_or(unknown, |source| &source[..]))?;
+    writeln!(writer, "{}: {}", t!("Built"), header.built.as_ref().map_or(unknown, |built| &built[..]))?;
+
+    let mut keys = 0;
+    let mut collisions = 0;
+    let mut colliding_words = 0;
+    let mut frequencies = Vec::new();
+    for (_, entries) in dictionary.keys() {
+        let entries = entries.collect::<Vec<_>>();
+        keys += 1;
+        if entries.len() > 1 {
+            collisions += 1;
+            colliding_words += entries.len();
+        }
+        frequencies.extend(entries.into_iter().map(|(_, frequency)| frequency));
+    }
+    frequencies.sort();
+
+    writeln!(writer, "{}: {}", t!("Entries"), frequencies.len())?;
+    writeln!(writer, "{}: {}", t!("Keys"), keys)?;
+    writeln!(writer, "{}: {} ({} {})", t!("Key collisions"), collisions, colliding_words, t!("entries"))?;
+    writeln!(writer, "{}: {}", t!("Next-word contexts"), dictionary.ngrams().count())?;
+    if frequencies.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "{}: {}", t!("Total frequency"), frequencies.iter().sum::<u64>())?;
+    writeln!(writer, "{}: {} / {} / {}", t!("Minimum / median / maximum frequency"), frequencies[0],
+             frequencies[frequencies.len() / 2], frequencies[frequencies.len() - 1])?;
+
+    // Entries per order of magnitude of their frequency.
+    let mut buckets = BTreeMap::new();
+    for &frequency in &frequencies {
+        *buckets.entry(frequency.to_string().len()).or_insert(0) += 1;
+    }
+    for (digits, count) in buckets {
+        let lower = 10u64.pow(digits as u32 - 1);
+        writeln!(writer, "{:>12} - {:<12} {}", if digits == 1 { 0 } else { lower }, lower * 10 - 1, count)?;
+    }
+    Ok(())
+}
+
+fn write_export<W: Write>(dictionary: &Dictionary, separator: &str, writer: &mut W) -> io::Result<()> {
+    for (word, frequency) in dictionary.words() {
+        writeln!(writer, "{}{}{}", word, separator, frequency)?;
+    }
+    Ok(())
+}
+
+fn write_diff<W: Write>(old_dictionary: &Dictionary, new_dictionary: &Dictionary, writer: &mut W) -> io::Result<()> {
+    let old = word_frequencies(old_dictionary);
+    let new = word_frequencies(new_dictionary);
+
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for (word, &frequency) in &old {
+        match new.get(word) {
+            None => {
+                writeln!(writer, "- {}\t{}", word, frequency)?;
+                removed += 1;
+            }
+            Some(&new_frequency) if new_frequency != frequency => {
+                writeln!(writer, "~ {}\t{} -> {}", word, frequency, new_frequency)?;
+                changed += 1;
+            }
+            Some(_) => {}
+        }
+    }
+    for (word, &frequency) in &new {
+        if !old.contains_key(word) {
+            writeln!(writer, "+ {}\t{}", word, frequency)?;
+            added += 1;
+        }
+    }
+    writeln!(writer, t!("{} added, {} removed, {} changed"), added, removed, changed)
+}
+
+pub fn lookup(matches: &ArgMatches) {
+    let dictionary = open(matches, "DICTIONARY");
+    write_lookup(&dictionary, matches.value_of("WORD").unwrap(), &mut io::stdout())
+        .unwrap_or_else(|error| fail(&error.to_string()));
+}
+
+pub fn prefix(matches: &ArgMatches) {
+    let dictionary = open(matches, "DICTIONARY");
+    let limit = value_t!(matches, "LIMIT", usize).unwrap_or_else(|error| error.exit());
+    write_prefix(&dictionary, matches.value_of("PREFIX").unwrap(), limit, &mut io::stdout())
+        .unwrap_or_else(|error| fail(&error.to_string()));
+}
+
+pub fn stats(matches: &ArgMatches) {
+    let dictionary = open(matches, "DICTIONARY");
+    write_stats(&dictionary, &mut io::stdout()).unwrap_or_else(|error| fail(&error.to_string()));
+}
+
+pub fn export(matches: &ArgMatches) {
+    let dictionary = open(matches, "DICTIONARY");
+    let separator = if matches.value_of("FORMAT") == Some("tab") { "\t" } else { "=" };
+    write_export(&dictionary, separator, &mut output(matches)).unwrap_or_else(|error| fail(&error.to_string()));
+}
+
+pub fn diff(matches: &ArgMatches) {
+    let old_dictionary = open(matches, "OLD");
+    let new_dictionary = open(matches, "NEW");
+    write_diff(&old_dictionary, &new_dictionary, &mut io::stdout()).unwrap_or_else(|error| fail(&error.to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_diff, write_export, write_lookup, write_prefix, write_stats};
+
+    use commcomm::decoder::Dictionary;
+    use commcomm::decoder::normalize;
+
+    use tempdir::TempDir;
+
+    use std::io;
+
+    fn dictionary(words: &[(&str, u64)]) -> Dictionary {
+        let mut dictionary = Dictionary::new();
+        for &(word, frequency) in words {
+            dictionary.insert(word, frequency);
+        }
+        dictionary
+    }
+
+    fn output<F: FnOnce(&mut Vec<u8>) -> io::Result<()>>(f: F) -> Vec<String> {
+        let mut bytes = Vec::new();
+        f(&mut bytes).unwrap();
+        String::from_utf8(bytes).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn lookup() {
+        let dictionary = dictionary(&[("hill", 30), ("Hill", 5), ("hi", 10)]);
+        assert_eq!(output(|writer| write_lookup(&dictionary, "HILL", writer)),
+                   vec![format!("{}: hill", t!("Key")), "hill\t30".to_string(), "Hill\t5".to_string()]);
+        assert_eq!(output(|writer| write_lookup(&dictionary, "there", writer)),
+                   vec![format!("{}: there", t!("Key")), t!("No entries found").to_string()]);
+    }
+
+    #[test]
+    fn prefix() {
+        let dictionary = dictionary(&[("him", 10), ("hill", 30), ("hike", 10), ("hi", 10), ("there", 50)]);
+        assert_eq!(output(|writer| write_prefix(&dictionary, "HI", 3, writer)), vec!["hill\t30", "hi\t10", "hike\t10"]);
+        assert!(output(|writer| write_prefix(&dictionary, "x", 3, writer)).is_empty());
+    }
+
+    #[test]
+    fn stats() {
+        let temp_dir = TempDir::new("commcomm-test").unwrap();
+        let path = temp_dir.path().join("words.dict");
+        let mut dictionary = dictionary(&[("hill", 30), ("Hill", 5), ("hi", 10)]);
+        dictionary.set_language(Some("nl-NL"));
+        dictionary.add_ngram(&["hi"], "there", 1);
+        dictionary.write_to_file(&path).unwrap();
+
+        let dictionary = Dictionary::from_file(&path).unwrap();
+        let lines = output(|writer| write_stats(&dictionary, writer));
+        for line in vec![
+            format!("{}: nl-NL", t!("Language")),
+            format!("{}: nl ({} {})", t!("Normalization"), t!("version"), normalize::VERSION),
+            format!("{}: 3", t!("Entries")),
+            format!("{}: 2", t!("Keys")),
+            format!("{}: 1 (2 {})", t!("Key collisions"), t!("entries")),
+            format!("{}: 1", t!("Next-word contexts")),
+            format!("{}: 45", t!("Total frequency")),
+            format!("{}: 5 / 10 / 30", t!("Minimum / median / maximum frequency")),
+            format!("{:>12} - {:<12} {}", 0, 9, 1),
+            format!("{:>12} - {:<12} {}", 10, 99, 2)
+        ] {
+            assert!(lines.contains(&line), "missing line: {}", line);
+        }
+    }
+
+    #[test]
+    fn export() {
+        let dictionary = dictionary(&[("hill", 30), ("hi", 10)]);
+        assert_eq!(output(|writer| write_export(&dictionary, "=", writer)), vec!["hi=10", "hill=30"]);
+        assert_eq!(output(|writer| write_export(&dictionary, "\t", writer)), vec!["hi\t10", "hill\t30"]);
+    }
+
+    #[test]
+    fn diff() {
+        let old = dictionary(&[("hill", 30), ("hi", 10), ("there", 5)]);
+        let new = dictionary(&[("hill", 31), ("hi", 10), ("hike", 2)]);
+        assert_eq!(output(|writer| write_diff(&old, &new, writer)),
+                   vec!["~ hill\t30 -> 31".to_string(), "- there\t5".to_string(), "+ hike\t2".to_string(),
+                        format!(t!("{} added, {} removed, {} changed"), 1, 1, 1)]);
+    }
+}
@@ -0,0 +1,386 @@
+#[macro_use] extern crate commcomm;
+#[macro_use] extern crate clap;
+#[cfg(test)] extern crate tempdir;
+
+use commcomm::decoder::{Dictionary, NGRAM_ORDER};
+use input::{for_each_line, read_input, Format};
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{PathBuf, Path};
+use std::process;
+
+mod input;
+mod inspect;
+mod scheme;
+
+fn fail(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "{}.", message);
+    process::exit(1);
+}
+
+fn report(problems: &[String]) {
+    for problem in problems {
+        let _ = writeln!(io::stderr(), "{}.", problem);
+    }
+}
+
+fn read_counts(source: &Path, format: Option<Format>, counts: &mut BTreeMap<String, u64>) {
+    let format = format.unwrap_or_else(|| Format::detect(source));
+    match read_input(source, format, counts) {
+        Ok(problems) => report(&problems),
+        Err(error) => fail(&format!(t!("Could not read '{}': {}"), source.display(), error))
+    }
+}
+
+fn read_dictionary(source: &Path) -> Dictionary {
+    if source.extension().map_or(false, |extension| extension == "dict") {
+        Dictionary::from_file(source).unwrap_or_else(|error| fail(&error.to_string()))
+    } else {
+        let mut counts = BTreeMap::new();
+        read_counts(source, None, &mut counts);
+        let mut dictionary = Dictionary::new();
+        for (word, frequency) in counts {
+            dictionary.insert(word, frequency);
+        }
+        dictionary
+    }
+}
+
+fn count_ngrams(source: &Path, dictionary: &mut Dictionary) {
+    let mut text = String::new();
+    let mut problems = Vec::new();
+    let result = for_each_line(source, false, &mut problems, |_, line| {
+        text.push_str(line);
+        text.push('\n');
+        Ok(())
+    });
+    if let Err(error) = result {
+        fail(&format!(t!("Could not read '{}': {}"), source.display(), error));
+    }
+    report(&problems);
+    add_ngrams(&text, dictionary);
+}
+
+// The dictionary folds the case of the context, so the predicted words keep their spelling from the corpus.
+fn add_ngrams(text: &str, dictionary: &mut Dictionary) {
+    for sentence in text.split(&['.', '!', '?'][..]) {
+        let words = sentence.split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '-')
+                            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+                            .filter(|word| !word.is_empty())
+                            .collect::<Vec<_>>();
+        for end in 1..words.len() {
+            for order in 2..NGRAM_ORDER + 1 {
+                if end + 1 >= order {
+                    dictionary.add_ngram(&words[end + 1 - order..end], words[end], 1);
+                }
+            }
+        }
+    }
+}
+
+fn build_dictionary(matches: &ArgMatches) {
+    let sources = matches.values_of("INPUT").unwrap().map(Path::new).collect::<Vec<_>>();
+    let format = matches.value_of("FORMAT").and_then(Format::parse);
+    let min_frequency = if matches.is_present("MIN_FREQUENCY") {
+        value_t!(matches, "MIN_FREQUENCY", u64).unwrap_or_else(|error| error.exit())
+    } else {
+        1
+    };
+    let top = if matches.is_present("TOP") {
+        Some(value_t!(matches, "TOP", usize).unwrap_or_else(|error| error.exit()))
+    } else {
+        None
+    };
+    let charset = matches.value_of("CHARSET").map(|charset| charset.to_lowercase().chars().collect::<Vec<_>>());
+
+    let mut counts = BTreeMap::new();
+    for source in &sources {
+        read_counts(source, format, &mut counts);
+    }
+
+    let mut entries = counts.into_iter().filter(|&(ref word, frequency)| {
+        frequency >= min_frequency && charset.as_ref().map_or(true, |charset| {
+            word.to_lowercase().chars().all(|c| charset.contains(&c))
+        })
+    }).collect::<Vec<_>>();
+    if let Some(top) = top {
+        entries.sort_by(|a, b| (b.1, &a.0).cmp(&(a.1, &b.0)));
+        entries.truncate(top);
+    }
+
+    let mut dictionary = Dictionary::new();
+    dictionary.set_language(matches.value_of("LANGUAGE"));
+    for (word, frequency) in entries {
+        dictionary.insert(word, frequency);
+    }
+    if let Some(corpora) = matches.values_of("CORPUS") {
+        for corpus in corpora {
+            count_ngrams(Path::new(corpus), &mut dictionary);
+        }
+    }
+    let source = sources.iter().map(|source| source.display().to_string()).collect::<Vec<_>>().join(", ");
+    dictionary.set_source(Some(matches.value_of("SOURCE").map_or(source, String::from)));
+
+    let dest = matches.value_of("OUTPUT").map_or_else(|| sources[0].with_extension("dict"), PathBuf::from);
+    dictionary.write_to_file(&dest).unwrap_or_else(|error| fail(&error.to_string()));
+}
+
+fn toml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn generate_scheme(matches: &ArgMatches) {
+    let sensor_count = value_t!(matches, "SENSORS", usize).unwrap_or_else(|error| error.exit());
+    let confirm = if matches.is_present("CONFIRM") {
+        Some(value_t!(matches, "CONFIRM", usize).unwrap_or_else(|error| error.exit()))
+    } else {
+        None
+    };
+    if let Some(confirm) = confirm {
+        if confirm >= sensor_count {
+            fail(&format!(t!("Sensor index out of range: {}"), confirm));
+        }
+    }
+
+    let efforts = matches.value_of("EFFORT").map_or_else(|| vec![1.0; sensor_count], |efforts| {
+        efforts.split(',').map(|effort| {
+            match effort.trim().parse::<f64>() {
+                Ok(effort) if effort.is_finite() && effort > 0.0 => effort,
+                _ => fail(&format!(t!("Invalid effort weight: {}"), effort))
+            }
+        }).collect()
+    });
+    if efforts.len() != sensor_count {
+        fail(t!("The number of effort weights must equal the number of sensors"));
+    }
+
+    let sensors = scheme::sensors(efforts, confirm);
+    if sensors.len() < 2 {
+        fail(t!("At least two sensors besides the confirm sensor are needed"));
+    }
+
+    let dictionary = read_dictionary(Path::new(matches.value_of("INPUT").unwrap()));
+    let mut frequencies = BTreeMap::new();
+    let mut spaces = 0;
+    for (word, frequency) in dictionary.words() {
+        for c in word.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+            *frequencies.entry(format!("append:{}", c)).or_insert(0) += frequency;
+        }
+        spaces += frequency;
+    }
+    if frequencies.is_empty() {
+        fail(t!("The input contains no characters"));
+    }
+    frequencies.insert("space".to_string(), spaces);
+
+    let sequences = scheme::sequences(frequencies, &sensors);
+
+    let total = sequences.iter().map(|&(_, _, weight)| weight).sum::<u64>() as f64;
+    let flexes = sequences.iter().map(|&(_, ref sequence, weight)| {
+        weight as f64 * sequence.len() as f64
+    }).sum::<f64>() / total;
+    let effort = sequences.iter().map(|&(_, ref sequence, weight)| {
+        weight as f64 * sequence.iter().map(|&sensor| {
+            sensors.iter().find(|&&(index, _)| index == sensor).unwrap().1
+        }).sum::<f64>()
+    }).sum::<f64>() / total;
+
+    let mut writer: Box<Write> = match matches.value_of("OUTPUT") {
+        Some(path) => Box::new(BufWriter::new(File::create(path).unwrap_or_else(|error| fail(&error.to_string())))),
+        None => Box::new(io::stdout())
+    };
+    let keystrokes = flexes + if confirm.is_some() { 1.0 } else { 0.0 };
+    let effort = if matches.is_present("EFFORT") { Some(effort) } else { None };
+    write_scheme(&sequences, flexes, keystrokes, effort, &mut writer).unwrap_or_else(|error| fail(&error.to_string()));
+}
+
+fn write_scheme<W: Write>(sequences: &[(String, Vec<usize>, u64)], flexes: f64, keystrokes: f64, effort: Option<f64>,
+                          writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "# {}", format!(t!("Expected sensor flexes per character: {:.2}"), flexes))?;
+    writeln!(writer, "# {}", format!(t!("Expected keystrokes per character: {:.2}"), keystrokes))?;
+    if let Some(effort) = effort {
+        writeln!(writer, "# {}", format!(t!("Expected effort per character: {:.2}"), effort))?;
+    }
+    writeln!(writer, "[decoder.scheme]")?;
+    for &(ref command, ref sequence, _) in sequences {
+        let sequence = sequence.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+        writeln!(writer, "{} = [{}]", toml_string(command), sequence)?;
+    }
+    writer.flush()
+}
+
+fn main() {
+    let matches = App::new(t!("commcomm-rs dictionary tool"))
+                      .version(crate_version!())
+                      .author(crate_authors!())
+                      .about(t!("Builds a dictionary file from word lists, frequency lists and text."))
+                      .setting(AppSettings::SubcommandsNegateReqs)
+                      .arg(Arg::with_name("OUTPUT")
+                               .short("o")
+                               .long("output")
+                               .value_name("FILE")
+                               .help(t!("Sets a custom output file"))
+                               .takes_value(true))
+                      .arg(Arg::with_name("CORPUS")
+                               .short("c")
+                               .long("corpus")
+                               .value_name("FILE")
+                               .help(t!("Adds next-word counts from a plain text corpus"))
+                               .takes_value(true)
+                               .multiple(true)
+                               .number_of_values(1))
+                      .arg(Arg::with_name("LANGUAGE")
+                               .short("l")
+                               .long("language")
+                               .value_name("TAG")
+                               .help(t!("Sets the language tag stored in the dictionary header, e.g. nl-NL"))
+                               .takes_value(true))
+                      .arg(Arg::with_name("SOURCE")
+                               .long("source")
+                               .value_name("DESCRIPTION")
+                               .help(t!("Sets the source description stored in the dictionary header"))
+                               .takes_value(true))
+                      .arg(Arg::with_name("FORMAT")
+                               .short("f")
+                               .long("format")
+                               .value_name("FORMAT")
+                               .help(t!("Sets the input format; by default it is detected for each file"))
+                               .possible_values(&["auto", "equals", "tab", "space", "hunspell", "text"])
+                               .default_value("auto"))
+                      .arg(Arg::with_name("MIN_FREQUENCY")
+                               .short("m")
+                               .long("min-frequency")
+                               .value_name("COUNT")
+                               .help(t!("Leaves out words that occur less often than this"))
+                               .takes_value(true))
+                      .arg(Arg::with_name("TOP")
+                               .short("n")
+                               .long("top")
+                               .value_name("COUNT")
+                               .help(t!("Keeps only this many of the most frequent words"))
+                               .takes_value(true))
+                      .arg(Arg::with_name("CHARSET")
+                               .long("charset")
+                               .value_name("CHARACTERS")
+                               .help(t!("Keeps only words made up of these characters, ignoring case"))
+                               .takes_value(true))
+                      .arg(Arg::with_name("INPUT")
+                               .help(t!("The input files to merge"))
+                               .required(true)
+                               .multiple(true))
+                      .subcommand(SubCommand::with_name("scheme")
+                                             .about(t!("Generates a frequency-optimal input scheme."))
+                                             .arg(Arg::with_name("SENSORS")
+                                                      .short("s")
+                                                      .long("sensors")
+                                                      .value_name("COUNT")
+                                                      .help(t!("Sets the number of sensors"))
+                                                      .takes_value(true)
+                                                      .required(true))
+                                             .arg(Arg::with_name("CONFIRM")
+                                                      .short("c")
+                                                      .long("confirm")
+                                                      .value_name("INDEX")
+                                                      .help(t!("Sets the confirm sensor, which is left out of the scheme"))
+                                                      .takes_value(true))
+                                             .arg(Arg::with_name("EFFORT")
+                                                      .short("e")
+                                                      .long("effort")
+                                                      .value_name("WEIGHTS")
+                                                      .help(t!("Sets a comma-separated effort weight for each sensor; the easiest sensors get the most frequent branches"))
+                                                      .takes_value(true))
+                                             .arg(Arg::with_name("OUTPUT")
+                                                      .short("o")
+                                                      .long("output")
+                                                      .value_name("FILE")
+                                                      .help(t!("Sets a custom output file"))
+                                                      .takes_value(true))
+                                             .arg(Arg::with_name("INPUT")
+                                                      .help(t!("The dictionary or word-frequency file to use"))
+                                                      .required(true)))
+                      .subcommand(SubCommand::with_name("lookup")
+                                             .about(t!("Shows the normalized key of a word and the entries that share it."))
+                                             .arg(Arg::with_name("DICTIONARY")
+                                                      .help(t!("The dictionary file to inspect"))
+                                                      .required(true))
+                                             .arg(Arg::with_name("WORD")
+                                                      .help(t!("The word to look up"))
+                                                      .required(true)))
+                      .subcommand(SubCommand::with_name("prefix")
+                                             .about(t!("Lists the completions of a prefix, most frequent first."))
+                                             .arg(Arg::with_name("LIMIT")
+                                                      .short("n")
+                                                      .long("limit")
+                                                      .value_name("COUNT")
+                                                      .help(t!("Sets the maximum number of completions"))
+                                                      .default_value("10"))
+                                             .arg(Arg::with_name("DICTIONARY")
+                                                      .help(t!("The dictionary file to inspect"))
+                                                      .required(true))
+                                             .arg(Arg::with_name("PREFIX")
+                                                      .help(t!("The prefix to complete"))
+                                                      .required(true)))
+                      .subcommand(SubCommand::with_name("stats")
+                                             .about(t!("Shows the header, entry counts and frequency distribution of a dictionary."))
+                                             .arg(Arg::with_name("DICTIONARY")
+                                                      .help(t!("The dictionary file to inspect"))
+                                                      .required(true)))
+                      .subcommand(SubCommand::with_name("export")
+                                             .about(t!("Writes a dictionary back to a plain frequency list."))
+                                             .arg(Arg::with_name("FORMAT")
+                                                      .short("f")
+                                                      .long("format")
+                                                      .value_name("FORMAT")
+                                                      .help(t!("Sets the output format"))
+                                                      .possible_values(&["equals", "tab"])
+                                                      .default_value("equals"))
+                                             .arg(Arg::with_name("OUTPUT")
+                                                      .short("o")
+                                                      .long("output")
+                                                      .value_name("FILE")
+                                                      .help(t!("Sets a custom output file"))
+                                                      .takes_value(true))
+                                             .arg(Arg::with_name("DICTIONARY")
+                                                      .help(t!("The dictionary file to export"))
+                                                      .required(true)))
+                      .subcommand(SubCommand::with_name("diff")
+                                             .about(t!("Compares the words and frequencies of two dictionaries."))
+                                             .arg(Arg::with_name("OLD")
+                                                      .help(t!("The old dictionary file"))
+                                                      .required(true))
+                                             .arg(Arg::with_name("NEW")
+                                                      .help(t!("The new dictionary file"))
+                                                      .required(true)))
+                      .get_matches();
+
+    match matches.subcommand() {
+        ("scheme", Some(matches)) => generate_scheme(matches),
+        ("lookup", Some(matches)) => inspect::lookup(matches),
+        ("prefix", Some(matches)) => inspect::prefix(matches),
+        ("stats", Some(matches)) => inspect::stats(matches),
+        ("export", Some(matches)) => inspect::export(matches),
+        ("diff", Some(matches)) => inspect::diff(matches),
+        _ => build_dictionary(&matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::add_ngrams;
+
+    use commcomm::decoder::Dictionary;
+
+    #[test]
+    fn ngrams() {
+        let mut dictionary = Dictionary::new();
+        add_ngrams("I met Anna. Then I met Anna. Anna left", &mut dictionary);
+        assert_eq!(dictionary.predict(&["met"]), vec![("Anna", 1.0)]);
+        assert_eq!(dictionary.predict(&["I", "met"]), vec![("Anna", 1.0)]);
+        assert_eq!(dictionary.predict(&["then"]), vec![("I", 1.0)]);
+        assert_eq!(dictionary.predict(&["anna"]), vec![("left", 1.0)]);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+struct Node {
+    weight: u64,
+    command: Option<String>,
+    children: Vec<Node>
+}
+
+// The sensors that can be used in a scheme, easiest first. The confirm sensor is left out.
+pub fn sensors(efforts: Vec<f64>, confirm: Option<usize>) -> Vec<(usize, f64)> {
+    let mut sensors = efforts.into_iter()
+                             .enumerate()
+                             .filter(|&(sensor, _)| Some(sensor) != confirm)
+                             .collect::<Vec<_>>();
+    sensors.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    sensors
+}
+
+// Builds a prefix-free sequence for each command that minimizes the expected number of flexes. The tree
+// is built on the frequencies alone: the effort weights only decide which sensor each branch is assigned
+// to, so that the most frequent branches get the easiest sensors.
+pub fn sequences(frequencies: BTreeMap<String, u64>, sensors: &[(usize, f64)]) -> Vec<(String, Vec<usize>, u64)> {
+    let nodes = frequencies.into_iter().map(|(command, frequency)| {
+        Node {
+            weight: frequency,
+            command: Some(command),
+            children: Vec::new()
+        }
+    }).collect();
+    let tree = huffman_tree(nodes, sensors.len());
+
+    let mut sequences = Vec::new();
+    assign_sequences(&tree, sensors, &mut Vec::new(), &mut sequences);
+    sequences.sort_by(|a, b| (b.2, &a.0).cmp(&(a.2, &b.0)));
+    sequences
+}
+
+fn huffman_tree(mut nodes: Vec<Node>, arity: usize) -> Node {
+    // Every merge replaces `arity` nodes by one, so empty leaves are added until the merges come out even.
+    while (nodes.len() - 1) % (arity - 1) != 0 {
+        nodes.push(Node {
+            weight: 0,
+            command: None,
+            children: Vec::new()
+        });
+    }
+
+    while nodes.len() > 1 {
+        nodes.sort_by(|a, b| b.weight.cmp(&a.weight));
+        let split = nodes.len() - arity;
+        let children = nodes.split_off(split);
+        nodes.push(Node {
+            weight: children.iter().map(|child| child.weight).sum::<u64>(),
+            command: None,
+            children: children
+        });
+    }
+
+    nodes.pop().unwrap()
+}
+
+fn assign_sequences(node: &Node, sensors: &[(usize, f64)], prefix: &mut Vec<usize>,
+                    sequences: &mut Vec<(String, Vec<usize>, u64)>) {
+    if let Some(ref command) = node.command {
+        sequences.push((command.clone(), prefix.clone(), node.weight));
+        return;
+    }
+
+    let mut children = node.children.iter().collect::<Vec<_>>();
+    children.sort_by(|a, b| b.weight.cmp(&a.weight));
+    for (child, &(sensor, _)) in children.into_iter().zip(sensors) {
+        prefix.push(sensor);
+        assign_sequences(child, sensors, prefix, sequences);
+        prefix.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sensors, sequences};
+
+    use std::collections::BTreeMap;
+
+    fn frequencies(entries: &[(&str, u64)]) -> BTreeMap<String, u64> {
+        entries.iter().map(|&(command, frequency)| (command.to_string(), frequency)).collect()
+    }
+
+    #[test]
+    fn prefix_free() {
+        let frequencies = frequencies(&[
+            ("append:e", 120), ("append:t", 90), ("append:a", 80), ("append:o", 75), ("append:i", 70),
+            ("append:n", 67), ("append:s", 63), ("append:h", 61), ("append:r", 60), ("append:d", 43),
+            ("space", 200)
+        ]);
+        let sequences = sequences(frequencies, &sensors(vec![1.0; 3], None));
+        assert_eq!(sequences.len(), 11);
+        for &(_, ref a, _) in &sequences {
+            assert!(!a.is_empty());
+            for &(_, ref b, _) in &sequences {
+                assert!(a == b || !b.starts_with(a));
+            }
+        }
+    }
+
+    #[test]
+    fn arity_padding() {
+        // Four commands on three sensors need one empty leaf, which must not end up in the scheme.
+        let sequences = sequences(frequencies(&[("a", 10), ("b", 5), ("c", 3), ("d", 1)]),
+                                  &sensors(vec![1.0; 3], None));
+        let sequences = sequences.into_iter().map(|(command, sequence, _)| (command, sequence)).collect::<Vec<_>>();
+        assert_eq!(sequences, vec![("a".to_string(), vec![0]), ("b".to_string(), vec![1]),
+                                   ("c".to_string(), vec![2, 0]), ("d".to_string(), vec![2, 1])]);
+    }
+
+    #[test]
+    fn confirm_sensor() {
+        let sensors = sensors(vec![3.0, 1.0, 2.0, 1.5], Some(1));
+        assert_eq!(sensors.iter().map(|&(sensor, _)| sensor).collect::<Vec<_>>(), vec![3, 2, 0]);
+
+        let sequences = sequences(frequencies(&[("a", 10), ("b", 5), ("c", 3), ("d", 1), ("e", 1)]), &sensors);
+        assert!(sequences.iter().all(|&(_, ref sequence, _)| !sequence.contains(&1)));
+        assert_eq!(sequences[0], ("a".to_string(), vec![3], 10));
+    }
+}
@@ -0,0 +1,55 @@
+#[macro_use] extern crate commcomm;
+#[macro_use] extern crate clap;
+
+use commcomm::stats;
+
+use clap::{App, Arg};
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process;
+
+fn fail(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "{}.", message);
+    process::exit(1);
+}
+
+fn main() {
+    let matches = App::new(t!("commcomm-rs statistics tool"))
+                      .version(crate_version!())
+                      .author(crate_authors!())
+                      .about(t!("Exports typing statistics as CSV or JSON"))
+                      .arg(Arg::with_name("FORMAT")
+                               .short("f")
+                               .long("format")
+                               .value_name("FORMAT")
+                               .help(t!("Sets the export format"))
+                               .possible_values(&["csv", "json"])
+                               .default_value("csv"))
+                      .arg(Arg::with_name("OUTPUT")
+                               .short("o")
+                               .long("output")
+                               .value_name("FILE")
+                               .help(t!("Sets the output file"))
+                               .takes_value(true))
+                      .arg(Arg::with_name("DIRECTORY")
+                               .help(t!("Sets the statistics directory"))
+                               .default_value("statistics"))
+                      .get_matches();
+
+    let sessions = stats::load_sessions(Path::new(matches.value_of("DIRECTORY").unwrap()))
+                         .unwrap_or_else(|error| fail(&error.to_string()));
+
+    let mut writer: Box<Write> = match matches.value_of("OUTPUT") {
+        Some(path) => Box::new(BufWriter::new(File::create(path).unwrap_or_else(|error| fail(&error.to_string())))),
+        None => Box::new(io::stdout())
+    };
+    let result = match matches.value_of("FORMAT").unwrap() {
+        "json" => stats::write_json(&sessions, &mut writer),
+        _ => stats::write_csv(&sessions, &mut writer)
+    };
+    if let Err(error) = result {
+        fail(&error.to_string());
+    }
+}
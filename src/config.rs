@@ -13,7 +13,9 @@ use std::path::{Path, PathBuf};
 pub struct Configuration {
     pub speech: Speech,
     pub arduino: Arduino,
-    pub decoder: Decoder
+    pub decoder: Decoder,
+    #[serde(default)]
+    pub statistics: Statistics
 }
 
 #[derive(Deserialize)]
@@ -46,15 +48,149 @@ pub struct ArduinoSensor {
 
 #[derive(Deserialize)]
 pub struct Decoder {
-    pub confirm: usize,
+    pub method: Option<String>,
+    pub mode: Option<String>,
+    pub confirm: Option<usize>,
+    pub confirm_timeout: Option<u64>,
+    #[serde(default)]
     pub scheme: HashMap<String, Vec<usize>>,
+    #[serde(default)]
+    pub layers: HashMap<String, HashMap<String, Vec<usize>>>,
+    #[serde(default)]
+    pub confirm_actions: HashMap<String, String>,
+    #[serde(default)]
+    pub formatting: DecoderFormatting,
+    pub fuzzy: Option<DecoderFuzzy>,
+    #[serde(default)]
+    pub morse: DecoderMorse,
+    #[serde(default)]
+    pub scan: DecoderScan,
     pub prediction: DecoderPrediction
 }
 
+#[derive(Deserialize)]
+pub struct DecoderFuzzy {
+    pub correction: Option<String>,
+    #[serde(default = "default_max_distance")]
+    pub max_distance: usize
+}
+
+#[derive(Deserialize)]
+pub struct DecoderFormatting {
+    #[serde(default = "default_true")]
+    pub capitalize: bool,
+    #[serde(default = "default_true")]
+    pub punctuation_spacing: bool
+}
+
+impl Default for DecoderFormatting {
+    fn default() -> DecoderFormatting {
+        DecoderFormatting {
+            capitalize: true,
+            punctuation_spacing: true
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DecoderMorse {
+    pub sensor: Option<usize>,
+    #[serde(default = "default_morse_unit")]
+    pub unit: u64,
+    #[serde(default = "default_true")]
+    pub adaptive: bool,
+    #[serde(default)]
+    pub codes: HashMap<String, String>
+}
+
+impl Default for DecoderMorse {
+    fn default() -> DecoderMorse {
+        DecoderMorse {
+            sensor: None,
+            unit: default_morse_unit(),
+            adaptive: true,
+            codes: HashMap::new()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DecoderScan {
+    pub sensor: Option<usize>,
+    #[serde(default = "default_scan_interval")]
+    pub interval: u64,
+    #[serde(default)]
+    pub dwell: u64,
+    #[serde(default = "default_true")]
+    pub auto_restart: bool,
+    pub grid: Option<Vec<Vec<String>>>
+}
+
+impl Default for DecoderScan {
+    fn default() -> DecoderScan {
+        DecoderScan {
+            sensor: None,
+            interval: default_scan_interval(),
+            dwell: 0,
+            auto_restart: true,
+            grid: None
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct DecoderPrediction {
+    pub language: Option<String>,
     pub dictionary: Option<PathBuf>,
-    pub suggestions: usize
+    pub user_dictionary: Option<PathBuf>,
+    pub suggestions: usize,
+    #[serde(default = "default_true")]
+    pub learn: bool,
+    #[serde(default = "default_weight")]
+    pub dictionary_weight: f64,
+    #[serde(default = "default_weight")]
+    pub user_dictionary_weight: f64
+}
+
+#[derive(Deserialize)]
+pub struct Statistics {
+    #[serde(default = "default_true")]
+    pub save: bool,
+    #[serde(default = "default_statistics_directory")]
+    pub directory: PathBuf
+}
+
+impl Default for Statistics {
+    fn default() -> Statistics {
+        Statistics {
+            save: true,
+            directory: default_statistics_directory()
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_max_distance() -> usize {
+    1
+}
+
+fn default_morse_unit() -> u64 {
+    150
+}
+
+fn default_scan_interval() -> u64 {
+    1000
+}
+
+fn default_statistics_directory() -> PathBuf {
+    PathBuf::from("statistics")
 }
 
 impl Configuration {
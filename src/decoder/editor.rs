@@ -0,0 +1,551 @@
+use config::Configuration;
+use error::*;
+use super::{capitalize, ends_sentence, is_opening, is_punctuation, Dictionary, Input, InputEvent, NGRAM_ORDER};
+
+use std::collections::{HashMap, VecDeque};
+use std::iter;
+use std::mem;
+use std::path::PathBuf;
+
+const HISTORY_LIMIT: usize = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    word: Vec<String>,
+    word_tail: Vec<String>,
+    line: Vec<Vec<String>>,
+    line_tail: Vec<Vec<String>>,
+    question: bool,
+    shift: bool,
+    caps_lock: bool
+}
+
+#[derive(Debug)]
+pub struct Editor {
+    dictionary: Option<Dictionary>,
+    user_dictionary: Option<(PathBuf, Dictionary)>,
+    dictionary_weight: f64,
+    user_dictionary_weight: f64,
+    dictionary_total: u64,
+    user_dictionary_total: u64,
+    unsaved: bool,
+    learn: bool,
+    suggestions: usize,
+    capitalize: bool,
+    punctuation_spacing: bool,
+    question: bool,
+    shift: bool,
+    caps_lock: bool,
+    word: Vec<String>,
+    word_tail: Vec<String>,
+    line: Vec<Vec<String>>,
+    line_tail: Vec<Vec<String>>,
+    undo_history: VecDeque<Snapshot>,
+    redo_history: Vec<Snapshot>
+}
+
+impl Editor {
+    pub fn new(config: &Configuration) -> Result<Editor> {
+        let dictionary = Dictionary::from_config(&config)?;
+        let user_dictionary = Dictionary::user_from_config(&config)?;
+        let dictionary_total = dictionary.as_ref().map_or(0, Dictionary::total);
+        let user_dictionary_total = user_dictionary.as_ref().map_or(0, |&(_, ref dictionary)| dictionary.total());
+
+        Ok(Editor {
+            dictionary: dictionary,
+            user_dictionary: user_dictionary,
+            dictionary_weight: config.decoder.prediction.dictionary_weight,
+            user_dictionary_weight: config.decoder.prediction.user_dictionary_weight,
+            dictionary_total: dictionary_total,
+            user_dictionary_total: user_dictionary_total,
+            unsaved: false,
+            learn: config.decoder.prediction.learn,
+            suggestions: config.decoder.prediction.suggestions,
+            capitalize: config.decoder.formatting.capitalize,
+            punctuation_spacing: config.decoder.formatting.punctuation_spacing,
+            question: false,
+            shift: false,
+            caps_lock: false,
+            word: Vec::new(),
+            word_tail: Vec::new(),
+            line: Vec::new(),
+            line_tail: Vec::new(),
+            undo_history: VecDeque::new(),
+            redo_history: Vec::new()
+        })
+    }
+
+    pub fn line(&self) -> String {
+        let word = self.word.iter().chain(&self.word_tail).cloned().collect::<Vec<_>>();
+        self.line.iter()
+                 .chain(iter::once(&word))
+                 .chain(&self.line_tail)
+                 .map(|word| word.concat())
+                 .collect::<Vec<_>>()
+                 .join(" ")
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.line.iter().map(|word| word.concat().chars().count() + 1).sum::<usize>() +
+        self.word.concat().chars().count()
+    }
+
+    pub fn word(&self) -> String {
+        self.word[self.opening_marks()..].concat()
+    }
+
+    // Opening brackets and quotes at the start of the current word, which aren't part of the word itself.
+    fn opening_marks(&self) -> usize {
+        self.word.iter().take_while(|letters| is_opening(letters) || *letters == "\"").count()
+    }
+
+    pub fn suggestions(&self) -> Vec<&str> {
+        if self.word.is_empty() && !self.word_tail.is_empty() {
+            return Vec::new();
+        }
+
+        let prefix = self.word();
+        let context = self.context();
+        let mut scores = HashMap::new();
+        for (dictionary, weight, total) in self.dictionaries() {
+            let candidates = if prefix.is_empty() {
+                dictionary.predict(&context)
+            } else {
+                let total = total.max(1) as f64;
+                dictionary.completions(&prefix).into_iter().map(|(word, frequency)| {
+                    (word, frequency as f64 / total)
+                }).collect()
+            };
+            for (word, score) in candidates {
+                *scores.entry(word).or_insert(0.0) += weight * score;
+            }
+        }
+
+        let mut scores = scores.into_iter().collect::<Vec<_>>();
+        scores.sort_by(|a, b| a.0.cmp(b.0));
+        scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        scores.into_iter().take(self.suggestions).map(|(word, _)| word).collect()
+    }
+
+    // The suggestions numbered the way `accept:N` picks them, for the status of the input methods.
+    pub fn suggestion_status(&self) -> Option<String> {
+        let suggestions = self.suggestions();
+        if suggestions.is_empty() {
+            return None;
+        }
+        let suggestions = suggestions.iter().enumerate().map(|(index, word)| format!("{}:{}", index, word)).collect::<Vec<_>>();
+        Some(format!("{}: {}", t!("Suggestions"), suggestions.join(" ")))
+    }
+
+    fn dictionaries(&self) -> Vec<(&Dictionary, f64, u64)> {
+        let mut dictionaries = Vec::new();
+        if let Some(ref dictionary) = self.dictionary {
+            dictionaries.push((dictionary, self.dictionary_weight, self.dictionary_total));
+        }
+        if let Some((_, ref dictionary)) = self.user_dictionary {
+            dictionaries.push((dictionary, self.user_dictionary_weight, self.user_dictionary_total));
+        }
+        dictionaries
+    }
+
+    pub fn prefix_likelihood(&self, prefix: &str) -> f64 {
+        self.dictionaries().into_iter().map(|(dictionary, weight, total)| {
+            let frequency = dictionary.prefix(prefix).into_iter().map(|(_, frequency)| frequency).sum::<u64>();
+            weight * frequency as f64 / total.max(1) as f64
+        }).sum()
+    }
+
+    fn learn(&mut self, word: &str) {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+        if !self.learn || word.is_empty() || self.user_dictionary.is_none() {
+            return;
+        }
+
+        let spelling = {
+            let known = self.dictionary.iter()
+                                       .chain(self.user_dictionary.as_ref().map(|&(_, ref dictionary)| dictionary))
+                                       .flat_map(|dictionary| dictionary.lookup(word))
+                                       .map(|(known, _)| known)
+                                       .collect::<Vec<_>>();
+            known.iter().find(|&&known| known == word)
+                 .or_else(|| known.iter().find(|&&known| known.to_lowercase() == word.to_lowercase()))
+                 .map_or_else(|| {
+                     if self.capitalize && self.sentence_start() {
+                         word.to_lowercase()
+                     } else {
+                         word.to_string()
+                     }
+                 }, |&known| known.to_string())
+        };
+
+        let context = self.context();
+        let (_, ref mut dictionary) = *self.user_dictionary.as_mut().unwrap();
+        dictionary.add(&spelling, 1);
+        for start in 0..context.len() {
+            dictionary.add_ngram(&context[start..], &spelling, 1);
+        }
+        self.user_dictionary_total += 1;
+        self.unsaved = true;
+    }
+
+    // Learned words are written out once per line and when the editor goes away, not after every word.
+    fn save(&mut self) {
+        if !self.unsaved {
+            return;
+        }
+        if let Some((ref path, ref dictionary)) = self.user_dictionary {
+            if let Err(error) = dictionary.write_to_file(path) {
+                warn!(t!("Could not save the user dictionary: {}."), error);
+                return;
+            }
+        }
+        self.unsaved = false;
+    }
+
+    pub fn question(&self) -> bool {
+        self.question
+    }
+
+    pub fn commit_line(&mut self) -> Option<String> {
+        self.save();
+        let mut line = self.line().trim_right().to_string();
+        self.word.clear();
+        self.word_tail.clear();
+        self.line.clear();
+        self.line_tail.clear();
+        let question = mem::replace(&mut self.question, false);
+        if line.is_empty() {
+            return None;
+        }
+
+        if !ends_sentence(&line) {
+            // A trailing comma, semicolon or colon makes way for the sentence mark.
+            let end = line.trim_right_matches(|c: char| c == ',' || c == ';' || c == ':').trim_right().len();
+            line.truncate(end);
+            line.push(if question { '?' } else { '.' });
+        }
+        Some(line)
+    }
+
+    pub fn undo(&mut self) -> bool {
+        if let Some(snapshot) = self.undo_history.pop_back() {
+            let current = self.snapshot();
+            self.redo_history.push(current);
+            self.restore(snapshot);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if let Some(snapshot) = self.redo_history.pop() {
+            let current = self.snapshot();
+            self.undo_history.push_back(current);
+            self.restore(snapshot);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            word: self.word.clone(),
+            word_tail: self.word_tail.clone(),
+            line: self.line.clone(),
+            line_tail: self.line_tail.clone(),
+            question: self.question,
+            shift: self.shift,
+            caps_lock: self.caps_lock
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.word = snapshot.word;
+        self.word_tail = snapshot.word_tail;
+        self.line = snapshot.line;
+        self.line_tail = snapshot.line_tail;
+        self.question = snapshot.question;
+        self.shift = snapshot.shift;
+        self.caps_lock = snapshot.caps_lock;
+    }
+
+    fn context(&self) -> Vec<String> {
+        let mut context = Vec::new();
+        for word in self.line.iter().rev().map(|word| word.concat()) {
+            if context.len() == NGRAM_ORDER - 1 || ends_sentence(&word) {
+                break;
+            }
+            let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+            if !word.is_empty() {
+                context.insert(0, word.to_string());
+            }
+        }
+        context
+    }
+
+    fn sentence_start(&self) -> bool {
+        self.line.iter()
+                 .rev()
+                 .map(|word| word.concat())
+                 .find(|word| !word.is_empty())
+                 .map_or(true, |word| ends_sentence(&word))
+    }
+
+    // A straight quote opens a quotation when the quotes before the cursor are balanced.
+    fn quote_opens(&self) -> bool {
+        let quotes = self.line.iter().flat_map(|word| word.iter()).chain(&self.word).filter(|&letters| letters == "\"");
+        quotes.count() % 2 == 0
+    }
+
+    fn append(&mut self, letters: String) -> InputEvent {
+        let shift = mem::replace(&mut self.shift, false);
+        let word_start = self.opening_marks() == self.word.len();
+        let letters = if self.caps_lock {
+            letters.to_uppercase()
+        } else if shift || (self.capitalize && word_start && self.sentence_start()) {
+            capitalize(&letters)
+        } else {
+            letters
+        };
+
+        let quote = letters == "\"";
+        if self.punctuation_spacing && (is_opening(&letters) || (quote && self.quote_opens())) {
+            if !word_start {
+                let word = mem::replace(&mut self.word, Vec::new());
+                self.line.push(word);
+            }
+            self.word.push(letters.clone());
+        } else if self.punctuation_spacing && (is_punctuation(&letters) || quote) {
+            if self.word.is_empty() {
+                if let Some(word) = self.line.pop() {
+                    self.word = word;
+                }
+            } else {
+                // The mark ends the word just like a space does.
+                let word = self.word.concat();
+                self.learn(&word);
+            }
+            self.word.push(letters.clone());
+            let word = mem::replace(&mut self.word, Vec::new());
+            self.line.push(word);
+        } else {
+            self.word.push(letters.clone());
+        }
+        InputEvent::Letters(letters)
+    }
+
+    fn move_left(&mut self) -> bool {
+        if let Some(letters) = self.word.pop() {
+            self.word_tail.insert(0, letters);
+            true
+        } else if let Some(word) = self.line.pop() {
+            let tail = mem::replace(&mut self.word_tail, Vec::new());
+            self.line_tail.insert(0, tail);
+            self.word = word;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn move_right(&mut self) -> bool {
+        if !self.word_tail.is_empty() {
+            let letters = self.word_tail.remove(0);
+            self.word.push(letters);
+            true
+        } else if !self.line_tail.is_empty() {
+            let word = mem::replace(&mut self.word, Vec::new());
+            self.line.push(word);
+            self.word_tail = self.line_tail.remove(0);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn move_word_left(&mut self) -> bool {
+        if !self.word.is_empty() {
+            let mut word = mem::replace(&mut self.word, Vec::new());
+            word.extend(self.word_tail.drain(..));
+            self.word_tail = word;
+            true
+        } else if let Some(word) = self.line.pop() {
+            let tail = mem::replace(&mut self.word_tail, word);
+            self.line_tail.insert(0, tail);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn move_word_right(&mut self) -> bool {
+        if !self.word_tail.is_empty() {
+            self.word.extend(self.word_tail.drain(..));
+            true
+        } else if !self.line_tail.is_empty() {
+            let word = mem::replace(&mut self.word, self.line_tail.remove(0));
+            self.line.push(word);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn cursor_event(&self, moved: bool) -> InputEvent {
+        if moved {
+            InputEvent::Cursor(self.cursor())
+        } else {
+            InputEvent::Illegal
+        }
+    }
+
+    pub fn execute_all(&mut self, inputs: Vec<Option<Input>>) -> Option<InputEvent> {
+        let mut events = inputs.into_iter().map(|input| {
+            input.map_or(InputEvent::Illegal, |input| self.execute(input))
+        }).collect::<Vec<_>>();
+        match events.len() {
+            0 => None,
+            1 => events.pop(),
+            _ => Some(InputEvent::Macro(events))
+        }
+    }
+
+    pub fn execute(&mut self, input: Input) -> InputEvent {
+        let snapshot = self.snapshot();
+        let event = self.apply(input);
+        self.record(snapshot, &event);
+        event
+    }
+
+    // Inputs that leave the text and its formatting state unchanged don't take up an undo step.
+    pub fn record(&mut self, snapshot: Snapshot, event: &InputEvent) {
+        match *event {
+            InputEvent::Undo | InputEvent::Redo => {}
+            _ if snapshot == self.snapshot() => {}
+            _ => {
+                if self.undo_history.len() == HISTORY_LIMIT {
+                    self.undo_history.pop_front();
+                }
+                self.undo_history.push_back(snapshot);
+                self.redo_history.clear();
+            }
+        }
+    }
+
+    pub fn apply(&mut self, input: Input) -> InputEvent {
+        match input {
+            Input::Append(letters) => self.append(letters),
+            Input::Delete => {
+                if self.word.pop().is_some() {
+                    InputEvent::DeleteLetter
+                } else if let Some(word) = self.line.pop() {
+                    self.word = word;
+                    InputEvent::DeleteLetter
+                } else {
+                    InputEvent::Illegal
+                }
+            }
+            Input::DeleteWord => {
+                // In the middle of a word the whole word goes, at its start the word before it.
+                if self.word.is_empty() {
+                    self.line.pop();
+                } else {
+                    self.word.clear();
+                    self.word_tail.clear();
+                }
+                InputEvent::DeleteWord
+            }
+            Input::DeleteLine => {
+                self.word.clear();
+                self.word_tail.clear();
+                self.line.clear();
+                self.line_tail.clear();
+                self.question = false;
+                InputEvent::DeleteLine
+            }
+            Input::Space => {
+                if self.word.is_empty() {
+                    InputEvent::Illegal
+                } else {
+                    let word = mem::replace(&mut self.word, Vec::new());
+                    let word_string = word.concat();
+                    self.learn(&word_string);
+                    self.line.push(word);
+                    InputEvent::Word(word_string)
+                }
+            }
+            Input::Enter => {
+                let word = self.word.iter().chain(&self.word_tail).cloned().collect::<String>();
+                self.learn(&word);
+                self.commit_line().map_or(InputEvent::Illegal, InputEvent::Line)
+            }
+            Input::Question => {
+                self.question = !self.question;
+                InputEvent::Question(self.question)
+            }
+            Input::Accept(index) => {
+                let opening_marks = self.opening_marks();
+                let word = self.suggestions().get(index).map(|word| {
+                    if self.caps_lock {
+                        word.to_uppercase()
+                    } else if self.capitalize && self.sentence_start() {
+                        capitalize(word)
+                    } else {
+                        word.to_string()
+                    }
+                });
+                if let Some(word) = word {
+                    self.word.truncate(opening_marks);
+                    self.word.push(word.clone());
+                    self.word_tail.clear();
+                    InputEvent::Completion(word)
+                } else {
+                    InputEvent::Illegal
+                }
+            }
+            Input::Undo => {
+                if self.undo() { InputEvent::Undo } else { InputEvent::Illegal }
+            }
+            Input::Redo => {
+                if self.redo() { InputEvent::Redo } else { InputEvent::Illegal }
+            }
+            Input::CursorLeft => {
+                let moved = self.move_left();
+                self.cursor_event(moved)
+            }
+            Input::CursorRight => {
+                let moved = self.move_right();
+                self.cursor_event(moved)
+            }
+            Input::WordLeft => {
+                let moved = self.move_word_left();
+                self.cursor_event(moved)
+            }
+            Input::WordRight => {
+                let moved = self.move_word_right();
+                self.cursor_event(moved)
+            }
+            Input::Shift => {
+                self.shift = !self.shift;
+                InputEvent::Shift(self.shift)
+            }
+            Input::CapsLock => {
+                self.caps_lock = !self.caps_lock;
+                InputEvent::CapsLock(self.caps_lock)
+            }
+            Input::Phrase(text) => InputEvent::Phrase(text),
+            Input::Macro(inputs) => {
+                InputEvent::Macro(inputs.into_iter().map(|input| self.apply(input)).collect())
+            }
+            Input::Correct(_) | Input::Layer(_) | Input::LayerOnce(_) => InputEvent::Illegal
+        }
+    }
+}
+
+impl Drop for Editor {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
@@ -0,0 +1,1086 @@
+pub mod morse;
+pub mod normalize;
+pub mod scan;
+mod editor;
+mod table;
+
+use arduino::Event;
+use config::Configuration;
+use error::*;
+use self::editor::Editor;
+use self::morse::Morse;
+use self::normalize::{primary_subtag, Normalizer};
+use self::scan::{Scan, ScanState};
+use self::table::{read_le, write_le, DictEntry, Table};
+
+pub use self::table::{Entries, Groups, Words};
+
+use chrono::UTC;
+
+use fst::raw::MmapReadOnly;
+
+use flate2::FlateReadExt;
+use serde_json;
+
+use std::collections::{Bound, BTreeMap, BTreeSet, HashMap};
+use std::collections::btree_map::Range;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::iter;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub enum Input {
+    Append(String),
+    Delete,
+    DeleteWord,
+    DeleteLine,
+    Space,
+    Enter,
+    Question,
+    Accept(usize),
+    Undo,
+    Redo,
+    CursorLeft,
+    CursorRight,
+    WordLeft,
+    WordRight,
+    Shift,
+    CapsLock,
+    Phrase(String),
+    Macro(Vec<Input>),
+    Layer(String),
+    LayerOnce(String),
+    Correct(usize)
+}
+
+impl Input {
+    fn parse(command: &str, suggestions: usize, table: &str) -> Result<Input> {
+        Ok(match command {
+            "question" => Input::Question,
+            "delete" => Input::Delete,
+            "delete_word" => Input::DeleteWord,
+            "delete_line" => Input::DeleteLine,
+            "space" => Input::Space,
+            "enter" => Input::Enter,
+            "undo" => Input::Undo,
+            "redo" => Input::Redo,
+            "cursor_left" => Input::CursorLeft,
+            "cursor_right" => Input::CursorRight,
+            "word_left" => Input::WordLeft,
+            "word_right" => Input::WordRight,
+            "shift" => Input::Shift,
+            "caps_lock" => Input::CapsLock,
+            accept if accept.starts_with("accept:") => match accept[7..].parse::<usize>() {
+                Ok(index) if index < suggestions => Input::Accept(index),
+                Ok(index) => bail!(t!("Suggestion index out of range: {}"), index),
+                Err(_) => bail!(t!("Unknown command in '{}': {}"), table, command)
+            },
+            correct if correct.starts_with("correct:") => match correct[8..].parse::<usize>() {
+                Ok(index) if index < suggestions => Input::Correct(index),
+                Ok(index) => bail!(t!("Suggestion index out of range: {}"), index),
+                Err(_) => bail!(t!("Unknown command in '{}': {}"), table, command)
+            },
+            append if append.starts_with("append:") => Input::Append(append[7..].to_string()),
+            layer if layer.starts_with("layer:") => Input::Layer(layer[6..].to_string()),
+            layer if layer.starts_with("layer_once:") => Input::LayerOnce(layer[11..].to_string()),
+            phrase if phrase.starts_with("phrase:") => {
+                let text = phrase[7..].trim();
+                if text.is_empty() {
+                    bail!(t!("Empty phrase in '{}'"), table);
+                }
+                Input::Phrase(text.to_string())
+            }
+            commands if commands.starts_with("macro:") => {
+                let inputs = split_commands(&commands[6..]).iter().map(|command| {
+                    Input::parse(command.trim(), suggestions, table)
+                }).collect::<Result<Vec<_>>>()?;
+                for input in &inputs {
+                    match *input {
+                        Input::Undo | Input::Redo | Input::Macro(_) => {
+                            bail!(t!("Macros in '{}' can not contain undo, redo or other macros: {}"), table, command)
+                        }
+                        _ => {}
+                    }
+                }
+                Input::Macro(inputs)
+            }
+            command => bail!(t!("Unknown command in '{}': {}"), table, command)
+        })
+    }
+
+    // Layers and corrections belong to the scheme decoder, the other input methods only drive the editor.
+    fn check_editor(&self, table: &str) -> Result<()> {
+        match *self {
+            Input::Layer(_) | Input::LayerOnce(_) | Input::Correct(_) => {
+                bail!(t!("Layers and corrections can not be used in '{}': {}"), table, self)
+            }
+            Input::Macro(ref inputs) => {
+                for input in inputs {
+                    input.check_editor(table)?;
+                }
+                Ok(())
+            }
+            _ => Ok(())
+        }
+    }
+
+    fn check_layers<T>(&self, layers: &HashMap<String, T>) -> Result<()> {
+        match *self {
+            Input::Layer(ref name) | Input::LayerOnce(ref name) if !layers.contains_key(name) => {
+                bail!(t!("Unknown layer: {}"), name)
+            }
+            Input::Macro(ref inputs) => {
+                for input in inputs {
+                    input.check_layers(layers)?;
+                }
+                Ok(())
+            }
+            _ => Ok(())
+        }
+    }
+}
+
+impl Display for Input {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match *self {
+            Input::Append(ref letters) => write!(fmt, "{}", letters),
+            Input::Accept(index) => write!(fmt, "accept:{}", index),
+            Input::Delete => write!(fmt, "delete"),
+            Input::DeleteWord => write!(fmt, "delete_word"),
+            Input::DeleteLine => write!(fmt, "delete_line"),
+            Input::Space => write!(fmt, "space"),
+            Input::Enter => write!(fmt, "enter"),
+            Input::Question => write!(fmt, "question"),
+            Input::Undo => write!(fmt, "undo"),
+            Input::Redo => write!(fmt, "redo"),
+            Input::CursorLeft => write!(fmt, "cursor_left"),
+            Input::CursorRight => write!(fmt, "cursor_right"),
+            Input::WordLeft => write!(fmt, "word_left"),
+            Input::WordRight => write!(fmt, "word_right"),
+            Input::Shift => write!(fmt, "shift"),
+            Input::CapsLock => write!(fmt, "caps_lock"),
+            Input::Phrase(ref text) => write!(fmt, "\"{}\"", text),
+            Input::Macro(_) => write!(fmt, "macro"),
+            Input::Layer(ref name) => write!(fmt, "layer:{}", name),
+            Input::LayerOnce(ref name) => write!(fmt, "layer_once:{}", name),
+            Input::Correct(index) => write!(fmt, "correct:{}", index)
+        }
+    }
+}
+
+pub const NGRAM_ORDER: usize = 3;
+const NGRAM_BACKOFF: f64 = 0.4;
+
+// Compact dictionary files start with this signature and a format version byte, followed by the lengths
+// of the JSON header, the word index, the word entries, the n-gram index and the n-gram entries as
+// little-endian 64-bit integers.
+const MAGIC: &'static [u8] = b"CCDICT\0";
+pub const FORMAT_VERSION: u8 = 2;
+const SECTIONS: usize = 4;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Header {
+    pub format_version: u8,
+    pub language: Option<String>,
+    pub normalization: String,
+    pub normalization_version: u32,
+    pub word_count: u64,
+    pub source: Option<String>,
+    pub built: Option<String>
+}
+
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    header: Header,
+    normalizer: Normalizer,
+    words: Table,
+    ngrams: Table
+}
+
+#[derive(Deserialize)]
+struct JsonDictionary {
+    words: BTreeMap<String, Vec<DictEntry>>,
+    #[serde(default)]
+    ngrams: BTreeMap<String, Vec<DictEntry>>
+}
+
+impl Dictionary {
+    pub fn new() -> Dictionary {
+        Dictionary::default()
+    }
+
+    pub fn from_file(path: &Path) -> Result<Dictionary> {
+        let mut file = File::open(path).chain_err(|| t!("Could not open the dictionary file"))?;
+        let mut magic = [0; 8];
+        if file.read_exact(&mut magic).is_ok() && &magic[..MAGIC.len()] == MAGIC {
+            if magic[MAGIC.len()] != FORMAT_VERSION {
+                bail!(t!("Unsupported dictionary format version: {}"), magic[MAGIC.len()]);
+            }
+            Dictionary::from_mapped_file(file)
+        } else {
+            file.seek(SeekFrom::Start(0)).chain_err(|| t!("Could not open the dictionary file"))?;
+            Dictionary::from_json_file(file)
+        }
+    }
+
+    fn from_mapped_file(mut file: File) -> Result<Dictionary> {
+        let mut lengths = [0; (SECTIONS + 1) * 8];
+        file.read_exact(&mut lengths).chain_err(|| t!("The dictionary file is truncated"))?;
+        let mut lengths = lengths.chunks(8).map(|length| read_le(length) as usize).collect::<Vec<_>>();
+        let header_length = lengths.remove(0);
+        let mmap = MmapReadOnly::open(&file).chain_err(|| t!("Could not open the dictionary file"))?;
+        let mut offset = MAGIC.len() + 1 + (SECTIONS + 1) * 8;
+        if lengths.iter().fold(offset.saturating_add(header_length), |end, &length| end.saturating_add(length)) > mmap.len() {
+            bail!(t!("The dictionary file is truncated"));
+        }
+
+        let mut header = vec![0; header_length];
+        file.read_exact(&mut header).chain_err(|| t!("The dictionary file is truncated"))?;
+        let header = serde_json::from_slice::<Header>(&header).chain_err(|| t!("Could not parse the dictionary header"))?;
+        offset += header_length;
+
+        let mut sections = lengths.into_iter().map(|length| {
+            let section = mmap.range(offset, length);
+            offset += length;
+            section
+        }).collect::<Vec<_>>().into_iter();
+        let words = Table::open(sections.next().unwrap(), sections.next().unwrap())?;
+        let ngrams = Table::open(sections.next().unwrap(), sections.next().unwrap())?;
+        Ok(Dictionary {
+            normalizer: Normalizer::new(header.language.as_ref().map(|language| &language[..])),
+            header: header,
+            words: words,
+            ngrams: ngrams
+        })
+    }
+
+    fn from_json_file(file: File) -> Result<Dictionary> {
+        let mut json = String::new();
+        BufReader::new(file).zlib_decode().read_to_string(&mut json).chain_err(|| t!("Could not open the dictionary file"))?;
+        serde_json::from_str::<JsonDictionary>(&json).or_else(|_| {
+            serde_json::from_str(&json).map(|words| {
+                JsonDictionary {
+                    words: words,
+                    ngrams: BTreeMap::new()
+                }
+            })
+        }).map(|dictionary| {
+            // The keys of old dictionaries were made with other rules.
+            let mut loaded = Dictionary {
+                words: Table::Tree(dictionary.words),
+                ngrams: Table::Tree(dictionary.ngrams),
+                ..Dictionary::default()
+            };
+            loaded.rekey();
+            loaded
+        }).chain_err(|| t!("Could not parse the dictionary"))
+    }
+
+    fn from_config(config: &Configuration) -> Result<Option<Dictionary>> {
+        config.decoder.prediction.dictionary.as_ref().map(|dict| {
+            Dictionary::from_file(dict).and_then(|mut dictionary| {
+                dictionary.configure(dict, config.decoder.prediction.language.as_ref().map(|language| &language[..]))?;
+                Ok(Some(dictionary))
+            })
+        }).unwrap_or_else(|| Ok(None))
+    }
+
+    fn user_from_config(config: &Configuration) -> Result<Option<(PathBuf, Dictionary)>> {
+        let prediction = &config.decoder.prediction;
+        let path = prediction.user_dictionary.clone().or_else(|| {
+            prediction.dictionary.as_ref().map(|dict| dict.with_extension("user.dict"))
+        });
+        let language = prediction.language.as_ref().map(|language| &language[..]);
+        path.map(|path| {
+            if path.exists() {
+                let mut dictionary = Dictionary::from_file(&path)?;
+                dictionary.configure(&path, language)?;
+                Ok(Some((path, dictionary)))
+            } else {
+                let mut dictionary = Dictionary::new();
+                dictionary.set_language(language);
+                dictionary.set_source(Some(t!("Learned words")));
+                Ok(Some((path, dictionary)))
+            }
+        }).unwrap_or_else(|| Ok(None))
+    }
+
+    // Words are looked up with the rules of the configured language. The keys of compact dictionaries are
+    // fixed when they are built, so those must match; dictionaries without a header are keyed again.
+    fn configure(&mut self, path: &Path, language: Option<&str>) -> Result<()> {
+        if self.header.format_version == 0 {
+            warn!(t!("The dictionary '{}' has no header, so its language and normalization can not be checked."),
+                  path.display());
+            if language.is_some() {
+                self.set_language(language);
+            }
+            return Ok(());
+        }
+
+        match (language, self.header.language.as_ref()) {
+            (Some(language), Some(dictionary_language)) if !same_language(language, dictionary_language) => {
+                bail!(t!("The dictionary '{}' is for language '{}', but the configured language is '{}'"),
+                      path.display(), dictionary_language, language);
+            }
+            (Some(language), None) => {
+                warn!(t!("The dictionary '{}' does not name its language, so it can not be checked against '{}'."),
+                      path.display(), language);
+            }
+            _ => {}
+        }
+
+        let normalizer = Normalizer::new(language.or(self.header.language.as_ref().map(|language| &language[..])));
+        let header = &self.header;
+        if header.normalization != normalizer.rules() || header.normalization_version != normalize::VERSION {
+            bail!(t!("The dictionary '{}' was built with normalization rules '{}' version {}, but rules '{}' version {} are in use. Please rebuild it"),
+                  path.display(), header.normalization, header.normalization_version, normalizer.rules(),
+                  normalize::VERSION);
+        }
+        self.normalizer = normalizer;
+        Ok(())
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
+    }
+
+    pub fn key(&self, word: &str) -> String {
+        self.normalizer.key(word)
+    }
+
+    // Switching languages can switch normalization rules, so the words and n-grams are filed under new keys.
+    pub fn set_language<S: Into<String>>(&mut self, language: Option<S>) {
+        self.header.language = language.map(Into::into);
+        self.normalizer = Normalizer::new(self.header.language.as_ref().map(|language| &language[..]));
+        self.rekey();
+    }
+
+    fn rekey(&mut self) {
+        let (words, ngrams) = {
+            let (words, ngrams) = self.tables();
+            (mem::replace(words, BTreeMap::new()), mem::replace(ngrams, BTreeMap::new()))
+        };
+        for (_, entries) in words {
+            for entry in entries {
+                self.add(&entry.1, entry.0);
+            }
+        }
+        for (key, entries) in ngrams {
+            let key = self.ngram_key(&key.split(' ').collect::<Vec<_>>());
+            let rekeyed = self.tables().1.entry(key).or_insert_with(Vec::new);
+            for entry in entries {
+                add_entry(rekeyed, &entry.1, entry.0);
+            }
+        }
+    }
+
+    pub fn set_source<S: Into<String>>(&mut self, source: Option<S>) {
+        self.header.source = source.map(Into::into);
+    }
+
+    // Changing a mapped dictionary copies it into memory, which also releases the file so that it
+    // can be replaced.
+    fn tables(&mut self) -> (&mut BTreeMap<String, Vec<DictEntry>>, &mut BTreeMap<String, Vec<DictEntry>>) {
+        (self.words.tree(), self.ngrams.tree())
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, word: S, frequency: u64) {
+        let word = word.into();
+        let key = self.normalizer.key(&word);
+        self.tables().0.entry(key).or_insert_with(Vec::new).push(DictEntry(frequency, word));
+    }
+
+    pub fn add(&mut self, word: &str, count: u64) {
+        let key = self.normalizer.key(word);
+        add_entry(self.tables().0.entry(key).or_insert_with(Vec::new), word, count);
+    }
+
+    pub fn add_ngram<S: AsRef<str>>(&mut self, context: &[S], word: &str, count: u64) {
+        let key = self.ngram_key(context);
+        add_entry(self.tables().1.entry(key).or_insert_with(Vec::new), word, count);
+    }
+
+    pub fn predict<S: AsRef<str>>(&self, context: &[S]) -> Vec<(&str, f64)> {
+        let mut predictions = Vec::<(&str, f64)>::new();
+        let mut weight = 1.0;
+        for start in 0..context.len() {
+            if let Some(entries) = self.ngrams.get(&self.ngram_key(&context[start..])) {
+                let entries = entries.collect::<Vec<_>>();
+                let total = entries.iter().map(|&(_, count)| count).sum::<u64>().max(1) as f64;
+                for (word, count) in entries {
+                    if !predictions.iter().any(|&(prediction, _)| prediction == word) {
+                        predictions.push((word, weight * count as f64 / total));
+                    }
+                }
+            }
+            weight *= NGRAM_BACKOFF;
+        }
+        predictions
+    }
+
+    // The words of the context are normalized like the words themselves.
+    fn ngram_key<S: AsRef<str>>(&self, context: &[S]) -> String {
+        context.iter().map(|word| self.normalizer.key(word.as_ref())).collect::<Vec<_>>().join(" ")
+    }
+
+    pub fn total(&self) -> u64 {
+        self.words().map(|(_, frequency)| frequency).sum()
+    }
+
+    pub fn lookup(&self, word: &str) -> Vec<(&str, u64)> {
+        self.words.get(&self.normalizer.key(word)).map_or_else(Vec::new, |entries| entries.collect())
+    }
+
+    pub fn prefix(&self, prefix: &str) -> Vec<(&str, u64)> {
+        self.iter_prefix(prefix).collect()
+    }
+
+    pub fn iter_prefix(&self, prefix: &str) -> Words {
+        let prefix = self.normalizer.key(prefix);
+        let upper = key_successor(&prefix).map_or(Bound::Unbounded, Bound::Excluded);
+        Words::new(self.words.groups(&Bound::Included(prefix), &upper))
+    }
+
+    pub fn range(&self, from: Bound<&str>, to: Bound<&str>) -> Words {
+        let (from, to) = (self.normalize_bound(from), self.normalize_bound(to));
+        Words::new(self.words.groups(&from, &to))
+    }
+
+    fn normalize_bound(&self, bound: Bound<&str>) -> Bound<String> {
+        match bound {
+            Bound::Included(word) => Bound::Included(self.normalizer.key(word)),
+            Bound::Excluded(word) => Bound::Excluded(self.normalizer.key(word)),
+            Bound::Unbounded => Bound::Unbounded
+        }
+    }
+
+    pub fn words(&self) -> Words {
+        Words::new(self.words.groups(&Bound::Unbounded, &Bound::Unbounded))
+    }
+
+    // The words grouped by their normalized key.
+    pub fn keys(&self) -> Groups {
+        self.words.groups(&Bound::Unbounded, &Bound::Unbounded)
+    }
+
+    // The next-word counts grouped by their context.
+    pub fn ngrams(&self) -> Groups {
+        self.ngrams.groups(&Bound::Unbounded, &Bound::Unbounded)
+    }
+
+    // The words starting with the prefix, most frequent first and in alphabetical order among equals.
+    pub fn completions(&self, prefix: &str) -> Vec<(&str, u64)> {
+        let mut entries = self.prefix(prefix);
+        entries.sort_by(|a, b| (b.1, a.0).cmp(&(a.1, b.0)));
+        entries
+    }
+
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<&str> {
+        self.completions(prefix).into_iter().take(limit).map(|(word, _)| word).collect()
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        let (word_index, word_entries) = self.words.to_bytes()?;
+        let (ngram_index, ngram_entries) = self.ngrams.to_bytes()?;
+        let mut header = self.header.clone();
+        header.format_version = FORMAT_VERSION;
+        header.normalization = self.normalizer.rules().to_string();
+        header.normalization_version = normalize::VERSION;
+        header.word_count = self.words().count() as u64;
+        header.built = Some(UTC::now().to_rfc3339());
+        let header = serde_json::to_vec(&header).chain_err(|| t!("Could not write the dictionary file"))?;
+
+        let sections = [header, word_index, word_entries, ngram_index, ngram_entries];
+        let mut prefix = MAGIC.to_vec();
+        prefix.push(FORMAT_VERSION);
+        for section in &sections {
+            write_le(&mut prefix, section.len() as u64, 8);
+        }
+
+        let temp_path = path.with_extension("tmp");
+        File::create(&temp_path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            writer.write_all(&prefix)?;
+            for section in &sections {
+                writer.write_all(section)?;
+            }
+            writer.flush()
+        }).and_then(|_| {
+            fs::rename(&temp_path, path)
+        }).chain_err(|| t!("Could not write the dictionary file"))
+    }
+}
+
+fn add_entry(entries: &mut Vec<DictEntry>, word: &str, count: u64) {
+    if let Some(entry) = entries.iter_mut().find(|entry| entry.1 == word) {
+        entry.0 += count;
+        return;
+    }
+    entries.push(DictEntry(count, word.to_string()));
+}
+
+fn key_successor(key: &str) -> Option<String> {
+    let mut chars = key.chars().collect::<Vec<_>>();
+    while let Some(last) = chars.pop() {
+        if let Some(next) = (last as u32 + 1..0x110000).filter_map(char::from_u32).next() {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+// Language tags match when their primary subtags do, so 'nl' accepts both 'nl-NL' and 'nl-BE'.
+fn same_language(a: &str, b: &str) -> bool {
+    primary_subtag(a) == primary_subtag(b)
+}
+
+// Splits the commands of a macro on semicolons. `\;` stands for a literal semicolon and `\\` for a backslash.
+// TOML basic strings take their own escapes first, so the keys spell these as `\\;` and `\\\\`.
+fn split_commands(commands: &str) -> Vec<String> {
+    let mut split = vec![String::new()];
+    let mut chars = commands.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ ';') | Some(escaped @ '\\') => split.last_mut().unwrap().push(escaped),
+                Some(other) => {
+                    split.last_mut().unwrap().push(c);
+                    split.last_mut().unwrap().push(other);
+                }
+                None => split.last_mut().unwrap().push(c)
+            },
+            ';' => split.push(String::new()),
+            c => split.last_mut().unwrap().push(c)
+        }
+    }
+    split
+}
+
+fn switches_layer(event: &InputEvent) -> bool {
+    match *event {
+        InputEvent::Layer(_) => true,
+        InputEvent::Macro(ref events) => events.iter().any(switches_layer),
+        _ => false
+    }
+}
+
+fn edit_distance(a: &[usize], b: &[usize]) -> usize {
+    let mut previous = (0..b.len() + 1).collect::<Vec<_>>();
+    for (i, x) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + if x == y { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Punctuation and closing brackets attach to the word before them, opening brackets to the word after
+// them. Straight quotes can do either, so the editor decides by counting them.
+fn is_punctuation(letters: &str) -> bool {
+    !letters.is_empty() && letters.chars().all(|c| ".,!?;:)]}”»".contains(c))
+}
+
+fn is_opening(letters: &str) -> bool {
+    !letters.is_empty() && letters.chars().all(|c| "([{“«".contains(c))
+}
+
+fn ends_sentence(word: &str) -> bool {
+    word.trim_right_matches(|c: char| ")]}”»\"".contains(c)).ends_with(&['.', '!', '?'][..])
+}
+
+fn capitalize(letters: &str) -> String {
+    let mut chars = letters.chars();
+    if let Some(c) = chars.next() {
+        c.to_uppercase().chain(chars).collect()
+    } else {
+        String::new()
+    }
+}
+
+type InputScheme = BTreeMap<Vec<usize>, Input>;
+
+const DEFAULT_LAYER: &'static str = "default";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    Illegal,
+    DeleteLetter,
+    DeleteWord,
+    DeleteLine,
+    Letters(String),
+    Word(String),
+    Line(String),
+    Question(bool),
+    Completion(String),
+    Undo,
+    Redo,
+    Cursor(usize),
+    Shift(bool),
+    CapsLock(bool),
+    Phrase(String),
+    Macro(Vec<InputEvent>),
+    Layer(String),
+    Corrections(Vec<String>)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Hint<'a> {
+    pub input: &'a Input,
+    pub remaining: usize
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    Sequence,
+    Chord
+}
+
+impl Mode {
+    fn parse(mode: &str) -> Result<Mode> {
+        match mode {
+            "sequence" => Ok(Mode::Sequence),
+            "chord" => Ok(Mode::Chord),
+            "morse" | "scan" => bail!(t!("'{}' is an input method, set 'decoder.method = {}' instead"), mode, mode),
+            mode => bail!(t!("Unknown decoder mode: {}"), mode)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Correction {
+    Auto,
+    Suggest
+}
+
+impl Correction {
+    fn parse(correction: &str) -> Result<Correction> {
+        match correction {
+            "auto" => Ok(Correction::Auto),
+            "suggest" => Ok(Correction::Suggest),
+            correction => bail!(t!("Unknown correction mode: {}"), correction)
+        }
+    }
+}
+
+const CORRECTION_PENALTY: f64 = 0.1;
+const CORRECTION_SMOOTHING: f64 = 0.01;
+
+pub trait InputMethod {
+    fn process_event_at(&mut self, event: &Event, time: Instant) -> Option<InputEvent>;
+    fn tick(&mut self, now: Instant) -> Option<InputEvent>;
+    fn line(&self) -> String;
+    fn cursor(&self) -> usize;
+    fn question(&self) -> bool;
+    fn status(&self) -> Vec<String>;
+
+    fn grid(&self) -> Option<(&[Vec<Input>], ScanState)> {
+        None
+    }
+
+    fn process_event(&mut self, event: &Event) -> Option<InputEvent> {
+        self.process_event_at(event, Instant::now())
+    }
+}
+
+pub fn from_config(config: &Configuration) -> Result<Box<InputMethod>> {
+    let method = match (config.decoder.method.as_ref(), config.decoder.mode.as_ref()) {
+        (Some(method), _) => &method[..],
+        // Morse code and scanning used to be decoder modes.
+        (None, Some(mode)) if mode == "morse" || mode == "scan" => {
+            warn!(t!("'decoder.mode = {}' is deprecated, use 'decoder.method = {}' instead."), mode, mode);
+            &mode[..]
+        }
+        (None, _) => "scheme"
+    };
+    match method {
+        "scheme" => Ok(Box::new(Decoder::new(config)?)),
+        "morse" => Ok(Box::new(Morse::new(config)?)),
+        "scan" => Ok(Box::new(Scan::new(config)?)),
+        method => bail!(t!("Unknown input method: {}"), method)
+    }
+}
+
+#[derive(Debug)]
+pub struct Decoder {
+    mode: Mode,
+    layers: HashMap<String, InputScheme>,
+    layer: String,
+    layer_once: Option<String>,
+    confirm_actions: HashMap<usize, Input>,
+    suggestions: usize,
+    correction: Option<Correction>,
+    max_distance: usize,
+    corrections: Vec<Input>,
+    confirm: Option<usize>,
+    confirm_count: usize,
+    timeout: Option<Duration>,
+    last_input: Option<Instant>,
+    flexed: BTreeSet<usize>,
+    chord: BTreeSet<usize>,
+    input: Vec<usize>,
+    editor: Editor
+}
+
+impl Decoder {
+    pub fn new(config: &Configuration) -> Result<Decoder> {
+        let mode = config.decoder.mode.as_ref().map_or(Ok(Mode::Sequence), |mode| Mode::parse(mode))?;
+        let confirm = config.decoder.confirm;
+        let timeout = config.decoder.confirm_timeout.map(Duration::from_millis);
+        if mode == Mode::Sequence && confirm.is_none() && timeout.is_none() {
+            bail!(t!("Either 'decoder.confirm' or 'decoder.confirm_timeout' must be set"));
+        }
+        let suggestions = config.decoder.prediction.suggestions;
+
+        let mut layer_schemes = vec![(DEFAULT_LAYER.to_string(), "decoder.scheme".to_string(), &config.decoder.scheme)];
+        for (name, scheme) in &config.decoder.layers {
+            if name == DEFAULT_LAYER {
+                bail!(t!("Layer defined more than once: {}"), name);
+            }
+            layer_schemes.push((name.clone(), format!("decoder.layers.{}", name), scheme));
+        }
+
+        let mut layers = HashMap::new();
+        for (name, table, commands) in layer_schemes {
+            let mut scheme = InputScheme::new();
+            for (command, input) in commands {
+                let command = Input::parse(command, suggestions, &table)?;
+
+                for &id in input {
+                    if id >= config.arduino.sensors.len() {
+                        bail!(t!("Sensor index out of range: {}") , id);
+                    }
+                    if Some(id) == confirm {
+                        bail!(t!("Sensor index in '{}' can not be equal to 'decoder.confirm'"), table);
+                    }
+                }
+
+                let mut input = input.clone();
+                if mode == Mode::Chord {
+                    input.sort();
+                    input.dedup();
+                }
+
+                if scheme.insert(input.clone(), command).is_some() {
+                    let input = input.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+                    bail!(t!("Input defined more than once in '{}': [{}]"), table, input);
+                }
+            }
+            layers.insert(name, scheme);
+        }
+
+        let mut confirm_actions = HashMap::new();
+        for (count, command) in &config.decoder.confirm_actions {
+            let count = match count.parse::<usize>() {
+                Ok(count) if count >= 2 => count,
+                _ => bail!(t!("Invalid confirm count in 'decoder.confirm_actions': {}"), count)
+            };
+            confirm_actions.insert(count, Input::parse(command, suggestions, "decoder.confirm_actions")?);
+        }
+
+        for input in layers.values().flat_map(|scheme| scheme.values()).chain(confirm_actions.values()) {
+            input.check_layers(&layers)?;
+        }
+
+        let (correction, max_distance) = match config.decoder.fuzzy {
+            Some(ref fuzzy) => {
+                let correction = fuzzy.correction.as_ref().map_or(Ok(Correction::Auto), |correction| {
+                    Correction::parse(correction)
+                })?;
+                (Some(correction), fuzzy.max_distance)
+            }
+            None => (None, 0)
+        };
+
+        Ok(Decoder {
+            mode: mode,
+            layers: layers,
+            layer: DEFAULT_LAYER.to_string(),
+            layer_once: None,
+            confirm_actions: confirm_actions,
+            suggestions: suggestions,
+            correction: correction,
+            max_distance: max_distance,
+            corrections: Vec::new(),
+            confirm: confirm,
+            confirm_count: 0,
+            timeout: timeout,
+            last_input: None,
+            flexed: BTreeSet::new(),
+            chord: BTreeSet::new(),
+            input: Vec::new(),
+            editor: Editor::new(config)?
+        })
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn layer(&self) -> &str {
+        &self.layer
+    }
+
+    fn scheme(&self) -> &InputScheme {
+        &self.layers[&self.layer]
+    }
+
+    pub fn suggestions(&self) -> Vec<&str> {
+        self.editor.suggestions()
+    }
+
+    pub fn corrections(&self) -> &[Input] {
+        &self.corrections
+    }
+
+    pub fn question(&self) -> bool {
+        self.editor.question()
+    }
+
+    pub fn commit_line(&mut self) -> Option<String> {
+        self.editor.commit_line()
+    }
+
+    pub fn process_input(&mut self, input: usize) -> Option<InputEvent> {
+        self.process_input_at(input, Instant::now())
+    }
+
+    pub fn process_input_at(&mut self, input: usize, time: Instant) -> Option<InputEvent> {
+        if Some(input) == self.confirm {
+            self.confirm()
+        } else {
+            self.confirm_count = 0;
+            self.input.push(input);
+            self.last_input = Some(time);
+            None
+        }
+    }
+
+    fn confirm(&mut self) -> Option<InputEvent> {
+        self.last_input = None;
+        self.confirm_count += 1;
+        let event = match self.confirm_count {
+            1 => {
+                let sequence = mem::replace(&mut self.input, Vec::new());
+                let input = self.scheme().get(&sequence).cloned();
+                match input {
+                    Some(input) => self.execute(input),
+                    None => self.correct(&sequence)
+                }
+            }
+            count => {
+                let input = self.confirm_actions.get(&count).cloned();
+                match input {
+                    Some(input) => self.execute(input),
+                    None => return None
+                }
+            }
+        };
+
+        // A one-shot layer lasts until an input is carried out, unless that input switched layers itself.
+        match event {
+            InputEvent::Corrections(_) | InputEvent::Illegal => {}
+            ref event if switches_layer(event) => {}
+            _ => {
+                if let Some(layer) = self.layer_once.take() {
+                    self.layer = layer;
+                }
+            }
+        }
+        if let InputEvent::Corrections(_) = event {} else {
+            self.corrections.clear();
+        }
+        Some(event)
+    }
+
+    fn correct(&mut self, sequence: &[usize]) -> InputEvent {
+        let correction = match self.correction {
+            Some(correction) if !sequence.is_empty() => correction,
+            _ => return InputEvent::Illegal
+        };
+
+        let word = self.editor.word();
+        let mut candidates = self.scheme().iter().filter_map(|(candidate, input)| {
+            let distance = edit_distance(sequence, candidate);
+            if distance > self.max_distance {
+                return None;
+            }
+            // Only letters are guessed: a slip should never end up as a command, let alone a correction.
+            let likelihood = match *input {
+                Input::Append(ref letters) => self.editor.prefix_likelihood(&format!("{}{}", word, letters)),
+                _ => return None
+            };
+            Some((input.clone(), CORRECTION_PENALTY.powi(distance as i32) * (likelihood + CORRECTION_SMOOTHING)))
+        }).collect::<Vec<_>>();
+        candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        candidates.truncate(self.suggestions);
+
+        match correction {
+            _ if candidates.is_empty() => InputEvent::Illegal,
+            Correction::Auto => {
+                let (input, _) = candidates.remove(0);
+                self.execute(input)
+            }
+            Correction::Suggest => {
+                self.corrections = candidates.into_iter().map(|(input, _)| input).collect();
+                InputEvent::Corrections(self.corrections.iter().map(ToString::to_string).collect())
+            }
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.editor.undo()
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.editor.redo()
+    }
+
+    fn execute(&mut self, input: Input) -> InputEvent {
+        let snapshot = self.editor.snapshot();
+        let event = self.apply(input);
+        self.editor.record(snapshot, &event);
+        event
+    }
+
+    fn apply(&mut self, input: Input) -> InputEvent {
+        match input {
+            Input::Correct(index) => {
+                match self.corrections.get(index).cloned() {
+                    Some(input) => self.apply(input),
+                    None => InputEvent::Illegal
+                }
+            }
+            Input::Layer(name) => {
+                self.layer_once = None;
+                self.layer = name.clone();
+                InputEvent::Layer(name)
+            }
+            Input::LayerOnce(name) => {
+                let previous = mem::replace(&mut self.layer, name.clone());
+                if self.layer_once.is_none() {
+                    self.layer_once = Some(previous);
+                }
+                InputEvent::Layer(name)
+            }
+            Input::Macro(inputs) => {
+                InputEvent::Macro(inputs.into_iter().map(|input| self.apply(input)).collect())
+            }
+            input => self.editor.apply(input)
+        }
+    }
+
+    pub fn predict_input(&self) -> Vec<&Input> {
+        self.prefix_range().map(|(_, input)| input).collect()
+    }
+
+    pub fn hints(&self) -> BTreeMap<usize, Vec<Hint>> {
+        let entered = self.entered().len();
+        let mut hints = BTreeMap::new();
+        for (sequence, input) in self.prefix_range() {
+            if let Some(&next) = sequence.get(entered) {
+                hints.entry(next).or_insert_with(Vec::new).push(Hint {
+                    input: input,
+                    remaining: sequence.len() - entered
+                });
+            }
+        }
+        hints
+    }
+
+    // The sensors entered so far. In chord mode that's the chord being held, in its sorted scheme order.
+    fn entered(&self) -> Vec<usize> {
+        if self.mode == Mode::Chord && !self.chord.is_empty() {
+            self.chord.iter().cloned().collect()
+        } else {
+            self.input.clone()
+        }
+    }
+
+    fn prefix_range(&self) -> Range<Vec<usize>, Input> {
+        let entered = self.entered();
+        let lower = Bound::Included(&entered[..]);
+        let upper = entered.split_last().and_then(|(last, rest)| {
+            last.checked_add(1).map(|last| rest.iter().cloned().chain(iter::once(last)).collect::<Vec<_>>())
+        });
+        match upper {
+            Some(ref upper) => self.scheme().range::<[_], _>((lower, Bound::Excluded(&upper[..]))),
+            None => self.scheme().range::<[_], _>((lower, Bound::Unbounded))
+        }
+    }
+}
+
+impl InputMethod for Decoder {
+    fn process_event_at(&mut self, event: &Event, time: Instant) -> Option<InputEvent> {
+        match (self.mode, event) {
+            (Mode::Sequence, &Event::SensorFlexed(id)) => self.process_input_at(id as usize, time),
+            (Mode::Sequence, &Event::SensorExtended(_)) => None,
+            (Mode::Chord, &Event::SensorFlexed(id)) => {
+                let id = id as usize;
+                if Some(id) == self.confirm {
+                    self.confirm()
+                } else {
+                    self.flexed.insert(id);
+                    self.chord.insert(id);
+                    None
+                }
+            }
+            (Mode::Chord, &Event::SensorExtended(id)) => {
+                self.flexed.remove(&(id as usize));
+                if self.flexed.is_empty() && !self.chord.is_empty() {
+                    // A released chord counts as the first confirm.
+                    self.input = mem::replace(&mut self.chord, BTreeSet::new()).into_iter().collect();
+                    self.confirm_count = 0;
+                    self.confirm()
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn tick(&mut self, now: Instant) -> Option<InputEvent> {
+        match (self.timeout, self.last_input) {
+            (Some(timeout), Some(last_input)) if now >= last_input && now.duration_since(last_input) >= timeout => {
+                self.confirm()
+            }
+            _ => None
+        }
+    }
+
+    fn line(&self) -> String {
+        self.editor.line()
+    }
+
+    fn cursor(&self) -> usize {
+        self.editor.cursor()
+    }
+
+    fn question(&self) -> bool {
+        self.editor.question()
+    }
+
+    fn status(&self) -> Vec<String> {
+        let mut status = iter::once(format!("{}: {}", t!("Layer"), self.layer)).chain(self.hints().iter().map(|(sensor, hints)| {
+            let inputs = hints.iter().map(|hint| hint.input.to_string()).collect::<Vec<_>>();
+            format!("{}: {}", sensor, inputs.join(" "))
+        })).collect::<Vec<_>>();
+        if !self.corrections.is_empty() {
+            let corrections = self.corrections.iter().map(ToString::to_string).collect::<Vec<_>>();
+            status.push(format!("{}: {}", t!("Corrections"), corrections.join(" ")));
+        }
+        status.extend(self.editor.suggestion_status());
+        status
+    }
+}
@@ -0,0 +1,207 @@
+use arduino::Event;
+use config::Configuration;
+use error::*;
+use super::{Input, InputEvent, InputMethod};
+use super::editor::Editor;
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+const CODES: &'static [(&'static str, &'static str)] = &[
+    (".-", "a"), ("-...", "b"), ("-.-.", "c"), ("-..", "d"), (".", "e"), ("..-.", "f"), ("--.", "g"),
+    ("....", "h"), ("..", "i"), (".---", "j"), ("-.-", "k"), (".-..", "l"), ("--", "m"), ("-.", "n"),
+    ("---", "o"), (".--.", "p"), ("--.-", "q"), (".-.", "r"), ("...", "s"), ("-", "t"), ("..-", "u"),
+    ("...-", "v"), (".--", "w"), ("-..-", "x"), ("-.--", "y"), ("--..", "z"),
+    ("-----", "0"), (".----", "1"), ("..---", "2"), ("...--", "3"), ("....-", "4"),
+    (".....", "5"), ("-....", "6"), ("--...", "7"), ("---..", "8"), ("----.", "9"),
+    (".-.-.-", "."), ("--..--", ","), ("..--..", "?"), ("-.-.--", "!"), (".----.", "'"),
+    ("-..-.", "/"), ("---...", ":"), ("-.-.-.", ";"), ("-...-", "="), ("-....-", "-"),
+    (".-.-.", "+"), (".-..-.", "\""), (".--.-.", "@")
+];
+
+// Durations are measured in units: a dot lasts one unit, a dash three units. Elements are
+// separated by one unit, letters by three units and words by seven units.
+const DASH_THRESHOLD: f64 = 2.0;
+const LETTER_GAP: f64 = 2.0;
+const WORD_GAP: f64 = 5.0;
+const ADAPTATION: f64 = 0.2;
+const MIN_UNIT: f64 = 0.02;
+// A single press moves the unit at most towards half or twice its length, so that an outlier can't throw it off.
+const MIN_SAMPLE: f64 = 0.5;
+const MAX_SAMPLE: f64 = 2.0;
+
+#[derive(Debug)]
+pub struct Morse {
+    codes: HashMap<String, Input>,
+    sensor: Option<usize>,
+    adaptive: bool,
+    unit: f64,
+    pressed: Option<Instant>,
+    released: Option<Instant>,
+    code: String,
+    word: bool,
+    editor: Editor
+}
+
+impl Morse {
+    pub fn new(config: &Configuration) -> Result<Morse> {
+        let suggestions = config.decoder.prediction.suggestions;
+        let editor = Editor::new(config)?;
+        let config = &config.decoder.morse;
+        let mut codes = CODES.iter().map(|&(code, letter)| {
+            (code.to_string(), Input::Append(letter.to_string()))
+        }).collect::<HashMap<_, _>>();
+        codes.insert("........".to_string(), Input::Delete);
+        codes.insert(".-.-".to_string(), Input::Enter);
+
+        // Custom codes replace the built-in ones, but not each other.
+        let mut custom = HashSet::new();
+        for (command, code) in &config.codes {
+            if code.is_empty() || code.chars().any(|c| c != '.' && c != '-') {
+                bail!(t!("Invalid Morse code in 'decoder.morse.codes': {}"), code);
+            }
+            if !custom.insert(code) {
+                bail!(t!("Input defined more than once in '{}': [{}]"), "decoder.morse.codes", code);
+            }
+            let input = Input::parse(command, suggestions, "decoder.morse.codes")?;
+            input.check_editor("decoder.morse.codes")?;
+            codes.insert(code.clone(), input);
+        }
+
+        if config.unit == 0 {
+            bail!(t!("The Morse unit duration must be greater than zero"));
+        }
+
+        Ok(Morse {
+            codes: codes,
+            sensor: config.sensor,
+            adaptive: config.adaptive,
+            unit: config.unit as f64 / 1000.0,
+            pressed: None,
+            released: None,
+            code: String::new(),
+            word: false,
+            editor: editor
+        })
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn unit(&self) -> Duration {
+        let millis = (self.unit * 1000.0).round() as u64;
+        Duration::from_millis(millis)
+    }
+
+    fn flex(&mut self, id: usize, time: Instant) -> Vec<Option<Input>> {
+        if self.sensor.map_or(false, |sensor| sensor != id) || self.pressed.is_some() {
+            return Vec::new();
+        }
+
+        let inputs = self.decode(time);
+        self.pressed = Some(time);
+        self.released = None;
+        inputs
+    }
+
+    fn release(&mut self, id: usize, time: Instant) {
+        if self.sensor.map_or(false, |sensor| sensor != id) {
+            return;
+        }
+
+        if let Some(pressed) = self.pressed.take() {
+            let units = self.units(pressed, time);
+            if units < DASH_THRESHOLD {
+                self.code.push('.');
+                self.adapt(units);
+            } else {
+                self.code.push('-');
+                self.adapt(units / 3.0);
+            }
+            self.released = Some(time);
+        }
+    }
+
+    fn decode(&mut self, now: Instant) -> Vec<Option<Input>> {
+        let gap = match self.released {
+            Some(released) => self.units(released, now),
+            None => return Vec::new()
+        };
+
+        let mut inputs = Vec::new();
+        if gap >= LETTER_GAP && !self.code.is_empty() {
+            let input = self.codes.get(&self.code).cloned();
+            self.code.clear();
+            match input {
+                Some(Input::Append(_)) => self.word = true,
+                Some(_) => self.word = false,
+                None => {}
+            }
+            inputs.push(input);
+        }
+        if gap >= WORD_GAP {
+            if self.word {
+                inputs.push(Some(Input::Space));
+            }
+            self.word = false;
+            self.released = None;
+        }
+        inputs
+    }
+
+    fn units(&self, from: Instant, to: Instant) -> f64 {
+        if to < from {
+            return 0.0;
+        }
+        let duration = to.duration_since(from);
+        (duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9) / self.unit
+    }
+
+    fn adapt(&mut self, units: f64) {
+        if self.adaptive {
+            let unit = self.unit * units.max(MIN_SAMPLE).min(MAX_SAMPLE);
+            self.unit = (self.unit * (1.0 - ADAPTATION) + unit * ADAPTATION).max(MIN_UNIT);
+        }
+    }
+}
+
+impl InputMethod for Morse {
+    fn process_event_at(&mut self, event: &Event, time: Instant) -> Option<InputEvent> {
+        match *event {
+            Event::SensorFlexed(id) => {
+                let inputs = self.flex(id as usize, time);
+                self.editor.execute_all(inputs)
+            }
+            Event::SensorExtended(id) => {
+                self.release(id as usize, time);
+                None
+            }
+        }
+    }
+
+    fn tick(&mut self, now: Instant) -> Option<InputEvent> {
+        let inputs = self.decode(now);
+        self.editor.execute_all(inputs)
+    }
+
+    fn line(&self) -> String {
+        self.editor.line()
+    }
+
+    fn cursor(&self) -> usize {
+        self.editor.cursor()
+    }
+
+    fn question(&self) -> bool {
+        self.editor.question()
+    }
+
+    fn status(&self) -> Vec<String> {
+        let unit = self.unit();
+        let unit = unit.as_secs() * 1000 + unit.subsec_nanos() as u64 / 1_000_000;
+        let mut status = vec![format!("{}: {}", t!("Morse"), self.code), format!("{}: {} ms", t!("Unit"), unit)];
+        status.extend(self.editor.suggestion_status());
+        status
+    }
+}
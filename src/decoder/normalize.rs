@@ -0,0 +1,92 @@
+use unicode_normalization::UnicodeNormalization;
+
+// Identifies the normalization rules. Bump it whenever they change, so that dictionaries built with the
+// old rules are rejected instead of giving wrong lookups.
+pub const VERSION: u32 = 3;
+
+const DEFAULT_RULES: &'static str = "default";
+
+// Language-specific replacements, applied to lowercase letters before decomposition.
+const LANGUAGES: &'static [(&'static str, &'static [(char, &'static str)])] = &[
+    ("nl", &[('ĳ', "ij")]),
+    ("de", &[('ß', "ss")])
+];
+
+// Letters without a decomposition that still have an obvious base form.
+const FOLDS: &'static [(char, &'static str)] = &[
+    ('æ', "ae"), ('ð', "d"), ('đ', "d"), ('ħ', "h"), ('ı', "i"), ('ĸ', "k"), ('ł', "l"), ('ŋ', "n"),
+    ('ø', "o"), ('œ', "oe"), ('þ', "th"), ('ŧ', "t")
+];
+
+// Characters that are left out of keys altogether.
+const IGNORED: &'static [char] = &['\'', '+', '-', '.', '/', '_', ' ', '·', 'ʼ', '’'];
+
+#[derive(Clone, Debug)]
+pub struct Normalizer {
+    rules: &'static str,
+    replacements: &'static [(char, &'static str)]
+}
+
+impl Normalizer {
+    pub fn new(language: Option<&str>) -> Normalizer {
+        let language = language.map(primary_subtag).unwrap_or_default();
+        match LANGUAGES.iter().find(|&&(name, _)| name == language) {
+            Some(&(name, replacements)) => {
+                Normalizer {
+                    rules: name,
+                    replacements: replacements
+                }
+            }
+            None => Normalizer::default()
+        }
+    }
+
+    pub fn rules(&self) -> &'static str {
+        self.rules
+    }
+
+    // Lowercases the word, applies the language's replacements, decomposes it (NFKD) to drop accents
+    // and split ligatures, folds the remaining special letters and removes punctuation.
+    pub fn key(&self, word: &str) -> String {
+        let replaced = word.chars().flat_map(char::to_lowercase).fold(String::new(), |mut replaced, c| {
+            match self.replacements.iter().find(|&&(letter, _)| letter == c) {
+                Some(&(_, replacement)) => replaced.push_str(replacement),
+                None => replaced.push(c)
+            }
+            replaced
+        });
+
+        replaced.nfkd()
+                .filter(|&c| !is_combining_mark(c))
+                .flat_map(char::to_lowercase)
+                .fold(String::new(), |mut key, c| {
+                    match FOLDS.iter().find(|&&(letter, _)| letter == c) {
+                        Some(&(_, fold)) => key.push_str(fold),
+                        None if IGNORED.contains(&c) => {}
+                        None => key.push(c)
+                    }
+                    key
+                })
+    }
+}
+
+impl Default for Normalizer {
+    fn default() -> Normalizer {
+        Normalizer {
+            rules: DEFAULT_RULES,
+            replacements: &[]
+        }
+    }
+}
+
+pub fn primary_subtag(tag: &str) -> String {
+    tag.split(|c: char| c == '-' || c == '_').next().unwrap_or("").to_lowercase()
+}
+
+fn is_combining_mark(c: char) -> bool {
+    match c {
+        '\u{300}'...'\u{36f}' | '\u{1ab0}'...'\u{1aff}' | '\u{1dc0}'...'\u{1dff}' |
+        '\u{20d0}'...'\u{20ff}' | '\u{fe20}'...'\u{fe2f}' => true,
+        _ => false
+    }
+}
@@ -0,0 +1,206 @@
+use arduino::Event;
+use config::Configuration;
+use error::*;
+use super::{Input, InputEvent, InputMethod};
+use super::editor::Editor;
+
+use std::time::{Duration, Instant};
+
+const GRID: &'static [&'static [&'static str]] = &[
+    &["space", "append:e", "append:t", "append:a", "append:o", "append:i"],
+    &["append:n", "append:s", "append:h", "append:r", "append:d", "append:l"],
+    &["append:u", "append:c", "append:m", "append:w", "append:f", "append:g"],
+    &["append:y", "append:p", "append:b", "append:v", "append:k", "append:j"],
+    &["append:x", "append:q", "append:z", "append:.", "append:,", "append:?"],
+    &["delete", "delete_word", "enter", "undo", "shift"]
+];
+
+// Number of times the cells of a row are scanned before scanning falls back to the rows.
+const COLUMN_CYCLES: usize = 2;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScanState {
+    Idle,
+    Rows(usize),
+    Columns(usize, usize)
+}
+
+#[derive(Debug)]
+pub struct Scan {
+    grid: Vec<Vec<Input>>,
+    sensor: Option<usize>,
+    interval: Duration,
+    dwell: Duration,
+    auto_restart: bool,
+    state: ScanState,
+    next_step: Option<Instant>,
+    pressed: Option<Instant>,
+    cycles: usize,
+    editor: Editor
+}
+
+impl Scan {
+    pub fn new(config: &Configuration) -> Result<Scan> {
+        let suggestions = config.decoder.prediction.suggestions;
+        let editor = Editor::new(config)?;
+        let config = &config.decoder.scan;
+        let commands = match config.grid {
+            Some(ref grid) => grid.iter().map(|row| row.iter().map(|command| &command[..]).collect()).collect(),
+            None => GRID.iter().map(|row| row.to_vec()).collect::<Vec<_>>()
+        };
+        let grid = commands.iter().map(|row| {
+            row.iter().map(|command| Input::parse(command, suggestions, "decoder.scan.grid")).collect::<Result<Vec<_>>>()
+        }).collect::<Result<Vec<_>>>()?;
+        if grid.is_empty() || grid.iter().any(|row| row.is_empty()) {
+            bail!(t!("The rows in 'decoder.scan.grid' can not be empty"));
+        }
+        for input in grid.iter().flat_map(|row| row.iter()) {
+            input.check_editor("decoder.scan.grid")?;
+        }
+        if config.interval == 0 {
+            bail!(t!("The scan interval must be greater than zero"));
+        }
+
+        Ok(Scan {
+            grid: grid,
+            sensor: config.sensor,
+            interval: Duration::from_millis(config.interval),
+            dwell: Duration::from_millis(config.dwell),
+            auto_restart: config.auto_restart,
+            state: ScanState::Idle,
+            next_step: None,
+            pressed: None,
+            cycles: 0,
+            editor: editor
+        })
+    }
+
+    pub fn grid(&self) -> &[Vec<Input>] {
+        &self.grid
+    }
+
+    pub fn state(&self) -> ScanState {
+        self.state
+    }
+
+    fn flex(&mut self, id: usize, time: Instant) -> Option<Input> {
+        if self.sensor.map_or(false, |sensor| sensor != id) {
+            return None;
+        }
+
+        if self.dwell == Duration::from_millis(0) {
+            self.select(time)
+        } else {
+            self.pressed = Some(time);
+            None
+        }
+    }
+
+    fn release(&mut self, id: usize) {
+        if self.sensor.map_or(true, |sensor| sensor == id) {
+            self.pressed = None;
+        }
+    }
+
+    fn advance(&mut self, now: Instant) -> Option<Input> {
+        let dwelled = self.pressed.map_or(false, |pressed| now >= pressed + self.dwell);
+        let input = if dwelled {
+            self.pressed = None;
+            self.select(now)
+        } else {
+            None
+        };
+
+        while let Some(next_step) = self.next_step {
+            if now < next_step {
+                break;
+            }
+            self.next_step = Some(next_step + self.interval);
+            self.step();
+        }
+        input
+    }
+
+    fn select(&mut self, time: Instant) -> Option<Input> {
+        self.next_step = Some(time + self.interval);
+        self.cycles = 0;
+        match self.state {
+            ScanState::Idle => {
+                self.state = ScanState::Rows(0);
+                None
+            }
+            ScanState::Rows(row) => {
+                self.state = ScanState::Columns(row, 0);
+                None
+            }
+            ScanState::Columns(row, column) => {
+                if self.auto_restart {
+                    self.state = ScanState::Rows(0);
+                } else {
+                    self.state = ScanState::Idle;
+                    self.next_step = None;
+                }
+                Some(self.grid[row][column].clone())
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        self.state = match self.state {
+            ScanState::Idle => ScanState::Idle,
+            ScanState::Rows(row) => ScanState::Rows((row + 1) % self.grid.len()),
+            ScanState::Columns(row, column) if column + 1 < self.grid[row].len() => {
+                ScanState::Columns(row, column + 1)
+            }
+            ScanState::Columns(row, _) => {
+                self.cycles += 1;
+                if self.cycles < COLUMN_CYCLES {
+                    ScanState::Columns(row, 0)
+                } else {
+                    self.cycles = 0;
+                    ScanState::Rows(row)
+                }
+            }
+        };
+    }
+}
+
+impl InputMethod for Scan {
+    fn process_event_at(&mut self, event: &Event, time: Instant) -> Option<InputEvent> {
+        match *event {
+            Event::SensorFlexed(id) => {
+                let input = self.flex(id as usize, time);
+                input.map(|input| self.editor.execute(input))
+            }
+            Event::SensorExtended(id) => {
+                self.release(id as usize);
+                None
+            }
+        }
+    }
+
+    fn tick(&mut self, now: Instant) -> Option<InputEvent> {
+        let input = self.advance(now);
+        input.map(|input| self.editor.execute(input))
+    }
+
+    fn line(&self) -> String {
+        self.editor.line()
+    }
+
+    fn cursor(&self) -> usize {
+        self.editor.cursor()
+    }
+
+    fn question(&self) -> bool {
+        self.editor.question()
+    }
+
+    fn status(&self) -> Vec<String> {
+        self.editor.suggestion_status().into_iter().collect()
+    }
+
+    fn grid(&self) -> Option<(&[Vec<Input>], ScanState)> {
+        Some((&self.grid, self.state))
+    }
+}
@@ -0,0 +1,215 @@
+use error::*;
+
+use fst::{IntoStreamer, Map, MapBuilder, Streamer};
+use fst::map::Stream;
+use fst::raw::{Fst, MmapReadOnly};
+
+use std::borrow::Cow;
+use std::collections::{Bound, BTreeMap};
+use std::collections::btree_map;
+use std::fmt::{self, Debug, Formatter};
+use std::slice;
+use std::str;
+
+#[derive(Debug, Deserialize)]
+pub struct DictEntry(pub u64, pub String);
+
+// A table maps normalized keys to the words that share them. Tables that are loaded from a compact
+// dictionary file stay memory-mapped: the keys are stored in an FST whose values are offsets into
+// an entry section of the form `count: u32, (frequency: u64, length: u32, word: [u8])*`.
+pub enum Table {
+    Tree(BTreeMap<String, Vec<DictEntry>>),
+    Mapped(Map, MmapReadOnly)
+}
+
+impl Table {
+    pub fn open(index: MmapReadOnly, entries: MmapReadOnly) -> Result<Table> {
+        let map = Fst::from_mmap(index).map(Map::from).chain_err(|| t!("Could not parse the dictionary"))?;
+        Ok(Table::Mapped(map, entries))
+    }
+
+    pub fn tree(&mut self) -> &mut BTreeMap<String, Vec<DictEntry>> {
+        if let Table::Mapped(..) = *self {
+            let mut tree = BTreeMap::new();
+            for (key, entries) in self.groups(&Bound::Unbounded, &Bound::Unbounded) {
+                let entries = entries.map(|(word, frequency)| DictEntry(frequency, word.to_string())).collect();
+                tree.insert(key.into_owned(), entries);
+            }
+            *self = Table::Tree(tree);
+        }
+
+        match *self {
+            Table::Tree(ref mut tree) => tree,
+            Table::Mapped(..) => unreachable!()
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<Entries> {
+        match *self {
+            Table::Tree(ref tree) => tree.get(key).map(|entries| Entries::Tree(entries.iter())),
+            Table::Mapped(ref map, ref entries) => map.get(key).map(|offset| Entries::mapped(bytes(entries), offset))
+        }
+    }
+
+    pub fn groups(&self, from: &Bound<String>, to: &Bound<String>) -> Groups {
+        match *self {
+            Table::Tree(ref tree) => Groups::Tree(tree.range::<str, _>((as_str(from), as_str(to)))),
+            Table::Mapped(ref map, ref entries) => {
+                let range = match *from {
+                    Bound::Included(ref key) => map.range().ge(key),
+                    Bound::Excluded(ref key) => map.range().gt(key),
+                    Bound::Unbounded => map.range()
+                };
+                let range = match *to {
+                    Bound::Included(ref key) => range.le(key),
+                    Bound::Excluded(ref key) => range.lt(key),
+                    Bound::Unbounded => range
+                };
+                Groups::Mapped(range.into_stream(), bytes(entries))
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        let mut index = MapBuilder::memory();
+        let mut entries = Vec::new();
+        for (key, group) in self.groups(&Bound::Unbounded, &Bound::Unbounded) {
+            let group = group.collect::<Vec<_>>();
+            index.insert(key.as_bytes(), entries.len() as u64).chain_err(|| t!("Could not write the dictionary file"))?;
+            write_le(&mut entries, group.len() as u64, 4);
+            for (word, frequency) in group {
+                write_le(&mut entries, frequency, 8);
+                write_le(&mut entries, word.len() as u64, 4);
+                entries.extend_from_slice(word.as_bytes());
+            }
+        }
+
+        let index = index.into_inner().chain_err(|| t!("Could not write the dictionary file"))?;
+        Ok((index, entries))
+    }
+}
+
+impl Default for Table {
+    fn default() -> Table {
+        Table::Tree(BTreeMap::new())
+    }
+}
+
+impl Debug for Table {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match *self {
+            Table::Tree(ref tree) => fmt.debug_tuple("Tree").field(tree).finish(),
+            Table::Mapped(ref map, _) => write!(fmt, "Mapped({} keys)", map.len())
+        }
+    }
+}
+
+pub enum Groups<'a> {
+    Tree(btree_map::Range<'a, String, Vec<DictEntry>>),
+    Mapped(Stream<'a>, &'a [u8])
+}
+
+impl<'a> Iterator for Groups<'a> {
+    type Item = (Cow<'a, str>, Entries<'a>);
+
+    fn next(&mut self) -> Option<(Cow<'a, str>, Entries<'a>)> {
+        match *self {
+            Groups::Tree(ref mut range) => range.next().map(|(key, entries)| {
+                (Cow::Borrowed(&key[..]), Entries::Tree(entries.iter()))
+            }),
+            Groups::Mapped(ref mut stream, bytes) => stream.next().map(|(key, offset)| {
+                (Cow::Owned(String::from_utf8_lossy(key).into_owned()), Entries::mapped(bytes, offset))
+            })
+        }
+    }
+}
+
+pub enum Entries<'a> {
+    Tree(slice::Iter<'a, DictEntry>),
+    Mapped(&'a [u8], usize)
+}
+
+impl<'a> Entries<'a> {
+    fn mapped(bytes: &'a [u8], offset: u64) -> Entries<'a> {
+        let offset = offset as usize;
+        let count = bytes.get(offset..offset + 4).map_or(0, read_le) as usize;
+        Entries::Mapped(bytes.get(offset + 4..).unwrap_or(&[]), count)
+    }
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = (&'a str, u64);
+
+    fn next(&mut self) -> Option<(&'a str, u64)> {
+        match *self {
+            Entries::Tree(ref mut entries) => entries.next().map(|entry| (&entry.1[..], entry.0)),
+            Entries::Mapped(ref mut bytes, ref mut remaining) => {
+                let data = *bytes;
+                if *remaining == 0 || data.len() < 12 {
+                    return None;
+                }
+                let frequency = read_le(&data[..8]);
+                let length = read_le(&data[8..12]) as usize;
+                if data.len() < 12 + length {
+                    return None;
+                }
+
+                *bytes = &data[12 + length..];
+                *remaining -= 1;
+                Some((str::from_utf8(&data[12..12 + length]).unwrap_or(""), frequency))
+            }
+        }
+    }
+}
+
+pub struct Words<'a> {
+    groups: Groups<'a>,
+    entries: Option<Entries<'a>>
+}
+
+impl<'a> Words<'a> {
+    pub fn new(groups: Groups<'a>) -> Words<'a> {
+        Words {
+            groups: groups,
+            entries: None
+        }
+    }
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = (&'a str, u64);
+
+    fn next(&mut self) -> Option<(&'a str, u64)> {
+        loop {
+            if let Some(entry) = self.entries.as_mut().and_then(|entries| entries.next()) {
+                return Some(entry);
+            }
+            match self.groups.next() {
+                Some((_, entries)) => self.entries = Some(entries),
+                None => return None
+            }
+        }
+    }
+}
+
+pub fn read_le(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |value, &byte| value << 8 | byte as u64)
+}
+
+pub fn write_le(buffer: &mut Vec<u8>, value: u64, size: usize) {
+    buffer.extend((0..size).map(|index| (value >> (index * 8)) as u8));
+}
+
+fn as_str(bound: &Bound<String>) -> Bound<&str> {
+    match *bound {
+        Bound::Included(ref key) => Bound::Included(&key[..]),
+        Bound::Excluded(ref key) => Bound::Excluded(&key[..]),
+        Bound::Unbounded => Bound::Unbounded
+    }
+}
+
+// The mapping stays valid for as long as the table lives: dictionary files are only ever replaced
+// by renaming a new file over them, which leaves the mapped data untouched.
+fn bytes(entries: &MmapReadOnly) -> &[u8] {
+    unsafe { entries.as_slice() }
+}
@@ -6,6 +6,7 @@ extern crate chrono;
 #[macro_use] extern crate conrod;
 #[macro_use] extern crate error_chain;
 extern crate flate2;
+extern crate fst;
 #[macro_use] extern crate lazy_static;
 #[macro_use] extern crate log;
 extern crate serde;
@@ -15,6 +16,7 @@ extern crate serial;
 extern crate serial_enumerate;
 extern crate tempdir;
 extern crate toml;
+extern crate unicode_normalization;
 extern crate wait_timeout;
 
 #[cfg(windows)] extern crate kernel32;
@@ -34,6 +36,7 @@ pub mod error;
 pub mod intern;
 pub mod platform;
 pub mod speech;
+pub mod stats;
 pub mod ui;
 
 mod glium {
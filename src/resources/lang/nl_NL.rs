@@ -18,6 +18,7 @@ macro_rules! t {
     ("Could not find token '{}'") => ("Kon het token '{}' niet vinden");
     ("Could not create a TTS voice") => ("Kon geen TTS-stem aanmaken");
     ("Error while speaking") => ("Fout bij het uitspreken van de tekst");
+    ("Could not speak the line: {}.") => ("Kon de regel niet uitspreken: {}.");
     ("Could not set the TTS voice") => ("Kon de TTS-stem niet instellen");
     ("Could not set the speech volume") => ("Kon het spraakvolume niet instellen");
     ("Could not set the speech rate") => ("Kon de spraaksnelheid niet instellen");
@@ -26,10 +27,59 @@ macro_rules! t {
     ("Could not open the dictionary file") => ("Kon het woordenboekbestand niet openen");
     ("Could not parse the dictionary") => ("Kon het woordenboek niet parseren");
     ("Could not write the dictionary file") => ("Kon het woordenboekbestand niet wegschrijven");
-    ("Unknown command in 'decoder.scheme': {}") => ("Onbekend commando in 'decoder.scheme': {}");
+    ("The dictionary file is truncated") => ("Het woordenboekbestand is afgekapt");
+    ("Unsupported dictionary format version: {}") => ("Niet-ondersteunde versie van het woordenboekformaat: {}");
+    ("Could not parse the dictionary header") => ("Kon de header van het woordenboek niet parseren");
+    ("The dictionary '{}' has no header, so its language and normalization can not be checked.") => ("Het woordenboek '{}' heeft geen header, dus de taal en normalisatie kunnen niet gecontroleerd worden.");
+    ("The dictionary '{}' does not name its language, so it can not be checked against '{}'.") => ("Het woordenboek '{}' noemt zijn taal niet, dus het kan niet tegen '{}' gecontroleerd worden.");
+    ("The dictionary '{}' is for language '{}', but the configured language is '{}'") => ("Het woordenboek '{}' is voor de taal '{}', maar de ingestelde taal is '{}'");
+    ("The dictionary '{}' was built with normalization rules '{}' version {}, but rules '{}' version {} are in use. Please rebuild it") => ("Het woordenboek '{}' werd gebouwd met normalisatieregels '{}' versie {}, maar regels '{}' versie {} worden gebruikt. Bouw het opnieuw");
+    ("Learned words") => ("Geleerde woorden");
+    ("Could not save the user dictionary: {}.") => ("Kon het gebruikerswoordenboek niet opslaan: {}.");
+    ("Could not save the typing statistics: {}.") => ("Kon de typstatistieken niet opslaan: {}.");
+    ("Could not read the statistics directory") => ("Kon de statistiekenmap niet lezen");
+    ("Could not read the statistics file") => ("Kon het statistiekenbestand niet lezen");
+    ("Could not write the statistics file") => ("Kon het statistiekenbestand niet wegschrijven");
+    ("Could not write the statistics export") => ("Kon de statistiekenexport niet wegschrijven");
+    ("commcomm-rs statistics tool") => ("commcomm-rs statistiekentool");
+    ("Exports typing statistics as CSV or JSON") => ("Exporteert typstatistieken als CSV of JSON");
+    ("Sets the export format") => ("Stelt het exportformaat in");
+    ("Sets the output file") => ("Stelt het uitvoerbestand in");
+    ("Sets the statistics directory") => ("Stelt de statistiekenmap in");
+    ("total") => ("totaal");
+    ("Characters per minute") => ("Tekens per minuut");
+    ("Words per minute") => ("Woorden per minuut");
+    ("Flexes per character") => ("Buigingen per teken");
+    ("Illegal inputs") => ("Ongeldige invoer");
+    ("Deletions") => ("Verwijderingen");
+    ("Seconds per word") => ("Seconden per woord");
+    ("Unknown command in '{}': {}") => ("Onbekend commando in '{}': {}");
+    ("Unknown decoder mode: {}") => ("Onbekende decodeermodus: {}");
+    ("Unknown input method: {}") => ("Onbekende invoermethode: {}");
+    ("'{}' is an input method, set 'decoder.method = {}' instead") => ("'{}' is een invoermethode, stel in plaats daarvan 'decoder.method = {}' in");
+    ("'decoder.mode = {}' is deprecated, use 'decoder.method = {}' instead.") => ("'decoder.mode = {}' is verouderd, gebruik in plaats daarvan 'decoder.method = {}'.");
+    ("Layers and corrections can not be used in '{}': {}") => ("Lagen en correcties kunnen niet gebruikt worden in '{}': {}");
+    ("Unknown correction mode: {}") => ("Onbekende correctiemodus: {}");
+    ("Invalid Morse code in 'decoder.morse.codes': {}") => ("Ongeldige morsecode in 'decoder.morse.codes': {}");
+    ("The Morse unit duration must be greater than zero") => ("De duur van een morse-eenheid moet groter zijn dan nul");
+    ("The rows in 'decoder.scan.grid' can not be empty") => ("De rijen in 'decoder.scan.grid' mogen niet leeg zijn");
+    ("The scan interval must be greater than zero") => ("Het scaninterval moet groter zijn dan nul");
+    ("Either 'decoder.confirm' or 'decoder.confirm_timeout' must be set") => ("Ofwel 'decoder.confirm' ofwel 'decoder.confirm_timeout' moet ingesteld zijn");
+    ("Invalid confirm count in 'decoder.confirm_actions': {}") => ("Ongeldig aantal bevestigingen in 'decoder.confirm_actions': {}");
     ("Sensor index out of range: {}") => ("Sensorindex buiten bereik: {}");
-    ("Sensor index in 'decoder.scheme' can not be equal to 'decoder.confirm'") => ("Sensorindex in 'decoder.scheme' mag niet gelijk zijn aan 'decoder.confirm'");
-    ("Input defined more than once: [{}]") => ("Invoer meerdere keren gedefinieerd: [{}]");
+    ("Sensor index in '{}' can not be equal to 'decoder.confirm'") => ("Sensorindex in '{}' mag niet gelijk zijn aan 'decoder.confirm'");
+    ("Input defined more than once in '{}': [{}]") => ("Invoer meerdere keren gedefinieerd in '{}': [{}]");
+    ("Layer defined more than once: {}") => ("Laag meerdere keren gedefinieerd: {}");
+    ("Unknown layer: {}") => ("Onbekende laag: {}");
+    ("Question") => ("Vraag");
+    ("Layer") => ("Laag");
+    ("Corrections") => ("Correcties");
+    ("Suggestions") => ("Suggesties");
+    ("Morse") => ("Morse");
+    ("Unit") => ("Eenheid");
+    ("Empty phrase in '{}'") => ("Lege zin in '{}'");
+    ("Macros in '{}' can not contain undo, redo or other macros: {}") => ("Macro's in '{}' mogen geen undo, redo of andere macro's bevatten: {}");
+    ("Suggestion index out of range: {}") => ("Suggestie-index buiten bereik: {}");
     ("Error while swapping buffers") => ("Fout bij het uitwisselen van de buffers");
     ("Could not create the window") => ("Kon het venster niet creëren");
     ("Window created. OpenGL version: {}.") => ("Venster gecreëerd. OpenGL-versie: {}.");
@@ -45,9 +95,64 @@ macro_rules! t {
     ("The configuration file contains syntax errors") => ("Het configuratiebestand bevat syntaxisfouten");
     ("The configuration file is invalid") => ("Het configuratiebestand is niet geldig");
     ("commcomm-rs dictionary tool") => ("commcomm-rs woordenboek tool");
-    ("Builds a dictionary file from word-frequency file.") => ("Genereert een woordenboekbestand van een woord-frequentielijst.");
+    ("Builds a dictionary file from word lists, frequency lists and text.") => ("Genereert een woordenboekbestand van woordenlijsten, frequentielijsten en tekst.");
     ("Sets a custom output file") => ("Stelt een aangepast uitvoerbestand in");
-    ("The input file to use") => ("Het invoerbestand om te gebruiken");
+    ("Adds next-word counts from a plain text corpus") => ("Voegt telling van volgende woorden toe uit een tekstcorpus");
+    ("Sets the language tag stored in the dictionary header, e.g. nl-NL") => ("Stelt de taalcode in die in de header van het woordenboek wordt opgeslagen, bv. nl-NL");
+    ("Sets the source description stored in the dictionary header") => ("Stelt de bronbeschrijving in die in de header van het woordenboek wordt opgeslagen");
+    ("The input files to merge") => ("De invoerbestanden om samen te voegen");
+    ("Sets the input format; by default it is detected for each file") => ("Stelt het invoerformaat in; standaard wordt het per bestand gedetecteerd");
+    ("Leaves out words that occur less often than this") => ("Laat woorden weg die minder vaak voorkomen dan dit");
+    ("Keeps only this many of the most frequent words") => ("Houdt alleen zoveel van de meest voorkomende woorden over");
+    ("Keeps only words made up of these characters, ignoring case") => ("Houdt alleen woorden over die uit deze tekens bestaan, ongeacht hoofdletters");
+    ("Could not read '{}': {}") => ("Kon '{}' niet lezen: {}");
+    ("The line is not valid UTF-8") => ("De regel is geen geldige UTF-8");
+    ("Expected a word followed by a frequency") => ("Verwachtte een woord gevolgd door een frequentie");
+    ("Expected a word") => ("Verwachtte een woord");
+    ("Invalid affix condition: {}") => ("Ongeldige affixvoorwaarde: {}");
+    ("Invalid affix rule") => ("Ongeldige affixregel");
+    ("Generates a frequency-optimal input scheme.") => ("Genereert een frequentie-optimaal invoerschema.");
+    ("The dictionary or word-frequency file to use") => ("Het woordenboek of de woord-frequentielijst om te gebruiken");
+    ("Sets the number of sensors") => ("Stelt het aantal sensoren in");
+    ("Sets the confirm sensor, which is left out of the scheme") => ("Stelt de bevestigingssensor in, die buiten het schema blijft");
+    ("Sets a comma-separated effort weight for each sensor; the easiest sensors get the most frequent branches") => ("Stelt een kommagescheiden moeitegewicht per sensor in; de makkelijkste sensoren krijgen de vaakst gebruikte takken");
+    ("Invalid effort weight: {}") => ("Ongeldig moeitegewicht: {}");
+    ("The number of effort weights must equal the number of sensors") => ("Het aantal moeitegewichten moet gelijk zijn aan het aantal sensoren");
+    ("At least two sensors besides the confirm sensor are needed") => ("Er zijn minstens twee sensoren naast de bevestigingssensor nodig");
+    ("The input contains no characters") => ("De invoer bevat geen tekens");
+    ("Expected sensor flexes per character: {:.2}") => ("Verwacht aantal sensorbuigingen per teken: {:.2}");
+    ("Expected keystrokes per character: {:.2}") => ("Verwacht aantal aanslagen per teken: {:.2}");
+    ("Expected effort per character: {:.2}") => ("Verwachte moeite per teken: {:.2}");
+    ("Shows the normalized key of a word and the entries that share it.") => ("Toont de genormaliseerde sleutel van een woord en de vermeldingen die deze delen.");
+    ("The dictionary file to inspect") => ("Het woordenboekbestand om te inspecteren");
+    ("The word to look up") => ("Het woord om op te zoeken");
+    ("Lists the completions of a prefix, most frequent first.") => ("Toont de aanvullingen van een voorvoegsel, de meest voorkomende eerst.");
+    ("Sets the maximum number of completions") => ("Stelt het maximale aantal aanvullingen in");
+    ("The prefix to complete") => ("Het voorvoegsel om aan te vullen");
+    ("Shows the header, entry counts and frequency distribution of a dictionary.") => ("Toont de header, het aantal vermeldingen en de frequentieverdeling van een woordenboek.");
+    ("Writes a dictionary back to a plain frequency list.") => ("Schrijft een woordenboek terug naar een gewone frequentielijst.");
+    ("Sets the output format") => ("Stelt het uitvoerformaat in");
+    ("The dictionary file to export") => ("Het woordenboekbestand om te exporteren");
+    ("Compares the words and frequencies of two dictionaries.") => ("Vergelijkt de woorden en frequenties van twee woordenboeken.");
+    ("The old dictionary file") => ("Het oude woordenboekbestand");
+    ("The new dictionary file") => ("Het nieuwe woordenboekbestand");
+    ("Key") => ("Sleutel");
+    ("No entries found") => ("Geen vermeldingen gevonden");
+    ("unknown") => ("onbekend");
+    ("Format version") => ("Formaatversie");
+    ("Language") => ("Taal");
+    ("Normalization") => ("Normalisatie");
+    ("version") => ("versie");
+    ("Source") => ("Bron");
+    ("Built") => ("Gebouwd");
+    ("Entries") => ("Vermeldingen");
+    ("Keys") => ("Sleutels");
+    ("Key collisions") => ("Sleutelbotsingen");
+    ("entries") => ("vermeldingen");
+    ("Next-word contexts") => ("Contexten voor volgende woorden");
+    ("Total frequency") => ("Totale frequentie");
+    ("Minimum / median / maximum frequency") => ("Minimale / mediane / maximale frequentie");
+    ("{} added, {} removed, {} changed") => ("{} toegevoegd, {} verwijderd, {} gewijzigd");
     ("Could not change the sensor thresholds") => ("Kon sensordrempelwaarden niet wijzigen");
     ("Waiting for Arduino thread to finish.") => ("Bezig met wachten op Arduino-thread.");
     ("Retrying in {} seconds.") => ("Opnieuw proberen over {} seconden.");
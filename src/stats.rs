@@ -0,0 +1,257 @@
+use arduino::Event;
+use config::Configuration;
+use decoder::InputEvent;
+use error::*;
+
+use chrono::Local;
+use serde_json;
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+// Pauses longer than this many seconds do not count as typing time.
+const IDLE_LIMIT: f64 = 30.0;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Session {
+    pub started: String,
+    pub active_seconds: f64,
+    pub flexes: u64,
+    pub events: u64,
+    pub illegal: u64,
+    pub deletions: u64,
+    pub characters: u64,
+    pub words: u64
+}
+
+impl Session {
+    pub fn total(sessions: &[Session]) -> Session {
+        let mut total = Session {
+            started: t!("total").to_string(),
+            ..Session::default()
+        };
+        for session in sessions {
+            total.active_seconds += session.active_seconds;
+            total.flexes += session.flexes;
+            total.events += session.events;
+            total.illegal += session.illegal;
+            total.deletions += session.deletions;
+            total.characters += session.characters;
+            total.words += session.words;
+        }
+        total
+    }
+
+    pub fn characters_per_minute(&self) -> f64 {
+        ratio(self.characters as f64, self.active_seconds / 60.0)
+    }
+
+    pub fn words_per_minute(&self) -> f64 {
+        ratio(self.words as f64, self.active_seconds / 60.0)
+    }
+
+    pub fn flexes_per_character(&self) -> f64 {
+        ratio(self.flexes as f64, self.characters as f64)
+    }
+
+    pub fn illegal_rate(&self) -> f64 {
+        ratio(self.illegal as f64, self.events as f64)
+    }
+
+    pub fn deletion_rate(&self) -> f64 {
+        ratio(self.deletions as f64, self.events as f64)
+    }
+
+    pub fn seconds_per_word(&self) -> f64 {
+        ratio(self.active_seconds, self.words as f64)
+    }
+
+    pub fn report(&self) -> Report {
+        Report {
+            started: self.started.clone(),
+            active_seconds: self.active_seconds,
+            flexes: self.flexes,
+            events: self.events,
+            illegal: self.illegal,
+            deletions: self.deletions,
+            characters: self.characters,
+            words: self.words,
+            characters_per_minute: self.characters_per_minute(),
+            words_per_minute: self.words_per_minute(),
+            flexes_per_character: self.flexes_per_character(),
+            illegal_rate: self.illegal_rate(),
+            deletion_rate: self.deletion_rate(),
+            seconds_per_word: self.seconds_per_word()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub started: String,
+    pub active_seconds: f64,
+    pub flexes: u64,
+    pub events: u64,
+    pub illegal: u64,
+    pub deletions: u64,
+    pub characters: u64,
+    pub words: u64,
+    pub characters_per_minute: f64,
+    pub words_per_minute: f64,
+    pub flexes_per_character: f64,
+    pub illegal_rate: f64,
+    pub deletion_rate: f64,
+    pub seconds_per_word: f64
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator > 0.0 { numerator / denominator } else { 0.0 }
+}
+
+#[derive(Debug)]
+pub struct Statistics {
+    session: Session,
+    path: Option<PathBuf>,
+    last_activity: Option<Instant>
+}
+
+impl Statistics {
+    pub fn new(config: &Configuration) -> Statistics {
+        let now = Local::now();
+        let path = if config.statistics.save {
+            Some(config.statistics.directory.join(format!("{}.json", now.format("%Y%m%d-%H%M%S"))))
+        } else {
+            None
+        };
+
+        Statistics {
+            session: Session {
+                started: now.to_rfc3339(),
+                ..Session::default()
+            },
+            path: path,
+            last_activity: None
+        }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn record_event(&mut self, event: &Event, time: Instant) {
+        self.touch(time);
+        if let Event::SensorFlexed(_) = *event {
+            self.session.flexes += 1;
+        }
+    }
+
+    pub fn record_input_event(&mut self, event: &InputEvent, time: Instant) {
+        self.touch(time);
+        self.count(event);
+        if let InputEvent::Line(_) = *event {
+            if let Err(error) = self.save() {
+                warn!(t!("Could not save the typing statistics: {}."), error);
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(())
+        };
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).chain_err(|| t!("Could not write the statistics file"))?;
+        }
+        File::create(path).chain_err(|| t!("Could not write the statistics file")).and_then(|file| {
+            serde_json::to_writer_pretty(&mut BufWriter::new(file), &self.session)
+                       .chain_err(|| t!("Could not write the statistics file"))
+        })
+    }
+
+    fn touch(&mut self, time: Instant) {
+        if let Some(last_activity) = self.last_activity {
+            if time >= last_activity {
+                let gap = time.duration_since(last_activity);
+                let gap = gap.as_secs() as f64 + gap.subsec_nanos() as f64 / 1e9;
+                if gap < IDLE_LIMIT {
+                    self.session.active_seconds += gap;
+                }
+            }
+        }
+        self.last_activity = Some(time);
+    }
+
+    // Characters and words are counted once a line is committed, so deleted text doesn't count. Phrases are
+    // spoken without entering the line and only count as an event, since they take a single input. Undo and
+    // redo can bring text back as well as take it away, so they aren't counted as deletions.
+    fn count(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::Macro(ref events) => {
+                for event in events {
+                    self.count(event);
+                }
+                return;
+            }
+            InputEvent::Illegal => {
+                self.session.illegal += 1;
+            }
+            InputEvent::DeleteLetter | InputEvent::DeleteWord | InputEvent::DeleteLine => {
+                self.session.deletions += 1;
+            }
+            InputEvent::Line(ref line) => {
+                self.session.characters += line.chars().count() as u64;
+                self.session.words += line.split_whitespace().count() as u64;
+            }
+            _ => {}
+        }
+        self.session.events += 1;
+    }
+}
+
+pub fn load_sessions(directory: &Path) -> Result<Vec<Session>> {
+    let mut paths = fs::read_dir(directory).and_then(|entries| {
+        entries.map(|entry| entry.map(|entry| entry.path())).collect::<::std::io::Result<Vec<_>>>()
+    }).chain_err(|| t!("Could not read the statistics directory"))?;
+    paths.retain(|path| path.extension().map_or(false, |extension| extension == "json"));
+    paths.sort();
+
+    paths.iter().map(|path| {
+        File::open(path).chain_err(|| t!("Could not read the statistics file")).and_then(|file| {
+            serde_json::from_reader(BufReader::new(file)).chain_err(|| t!("Could not read the statistics file"))
+        })
+    }).collect()
+}
+
+pub fn write_csv<W: Write>(sessions: &[Session], writer: &mut W) -> Result<()> {
+    let mut rows = vec![
+        "started,active_seconds,flexes,events,illegal,deletions,characters,words,characters_per_minute,\
+         words_per_minute,flexes_per_character,illegal_rate,deletion_rate,seconds_per_word".to_string()
+    ];
+    for session in sessions.iter().cloned().chain(Some(Session::total(sessions))) {
+        let report = session.report();
+        rows.push(format!("{},{:.1},{},{},{},{},{},{},{:.2},{:.2},{:.2},{:.4},{:.4},{:.2}",
+                          report.started, report.active_seconds, report.flexes, report.events,
+                          report.illegal, report.deletions, report.characters, report.words,
+                          report.characters_per_minute, report.words_per_minute,
+                          report.flexes_per_character, report.illegal_rate, report.deletion_rate,
+                          report.seconds_per_word));
+    }
+    writeln!(writer, "{}", rows.join("\n")).chain_err(|| t!("Could not write the statistics export"))
+}
+
+pub fn write_json<W: Write>(sessions: &[Session], writer: &mut W) -> Result<()> {
+    #[derive(Serialize)]
+    struct Export {
+        sessions: Vec<Report>,
+        total: Report
+    }
+
+    let export = Export {
+        sessions: sessions.iter().map(Session::report).collect(),
+        total: Session::total(sessions).report()
+    };
+    serde_json::to_writer_pretty(writer, &export).chain_err(|| t!("Could not write the statistics export"))
+}
@@ -58,6 +58,9 @@ impl App for Speech {
     }
 
     fn process_line(&mut self, line: &str) {
+        if let Err(error) = self.voice.speak(line) {
+            error!(t!("Could not speak the line: {}."), error);
+        }
         self.lines.push_front(Line {
             time: Local::now().time(),
             text: line.to_string()
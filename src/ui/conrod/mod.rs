@@ -2,8 +2,9 @@ use self::apps::*;
 use self::window::Window;
 use arduino::thread::ArduinoController;
 use config::Configuration;
-use decoder::Decoder;
+use decoder::InputMethod;
 use error::*;
+use stats::Statistics;
 
 use std::thread;
 use std::time::Duration;
@@ -11,12 +12,17 @@ use std::time::Duration;
 mod apps;
 mod window;
 
-pub fn run(_: Configuration, _: ArduinoController, mut decoder: Decoder) -> Result<()> {
+pub fn run(config: Configuration, arduino: ArduinoController, mut decoder: Box<InputMethod>) -> Result<()> {
+    let mut statistics = Statistics::new(&config);
     let mut window = Window::new(&[&Speech::new_app, &Editor::new_app])?;
-    while window.update(&mut decoder)? {
+    while window.update(&mut *decoder, &arduino, &mut statistics)? {
         thread::sleep(Duration::from_millis(1));
     }
     info!(t!("The window was closed."));
 
+    if let Err(error) = statistics.save() {
+        warn!(t!("Could not save the typing statistics: {}."), error);
+    }
+
     Ok(())
 }
@@ -1,6 +1,10 @@
 use super::apps::{App, AppFactory};
-use decoder::Decoder;
+use arduino::Event;
+use arduino::thread::ArduinoController;
+use decoder::{InputEvent, InputMethod};
+use decoder::scan::ScanState;
 use error::*;
+use stats::Statistics;
 
 use conrod::color;
 use conrod::{Colorable, Labelable, Positionable, Sizeable, Theme, Ui, UiBuilder};
@@ -10,7 +14,7 @@ use conrod::image::Map;
 use conrod::event::Input;
 use conrod::text::{self, FontCollection};
 use conrod::theme::WidgetDefault;
-use conrod::widget::{list_select, title_bar, Button, Canvas, ListSelect, TextBox, TitleBar, Widget};
+use conrod::widget::{list_select, title_bar, Button, Canvas, ListSelect, Matrix, Rectangle, Text, TitleBar, Widget};
 
 use glium::{DisplayBuild, Display};
 use glium::debug::{DebugCallbackBehavior, MessageType, Severity, Source};
@@ -20,7 +24,9 @@ use glutin::{self, Api, ElementState, Event as GlutinEvent, GlRequest, VirtualKe
 use log::LogLevel;
 
 use std::any::TypeId;
+use std::collections::BTreeSet;
 use std::mem;
+use std::time::Instant;
 
 
 
@@ -42,7 +48,13 @@ widget_ids! {
         MODE_TABS,
         CONTROL_CANVAS,
         CONTROL_TITLE,
-        INPUT_LINE
+        CONTROL_HINTS,
+        SCAN_GRID,
+        INPUT_LINE,
+        INPUT_BEFORE,
+        INPUT_CURSOR,
+        INPUT_AFTER,
+        QUESTION
     }
 }
 
@@ -54,7 +66,8 @@ pub struct Window {
     image_map: Map<Texture2d>,
     widgets: Widgets,
     apps: Vec<Box<App>>,
-    active_app: usize
+    active_app: usize,
+    pressed_keys: BTreeSet<u8>
 }
 
 impl Window {
@@ -116,7 +129,8 @@ impl Window {
             image_map: Map::new(),
             widgets: widgets,
             apps: apps,
-            active_app: 0
+            active_app: 0,
+            pressed_keys: BTreeSet::new()
         })
     }
 
@@ -163,9 +177,11 @@ impl Window {
         Ok(())
     }
 
-    fn handle_events(&mut self, decoder: &mut Decoder) -> Result<bool> {
+    fn handle_events(&mut self, decoder: &mut InputMethod, arduino: &ArduinoController,
+                     statistics: &mut Statistics) -> Result<bool> {
         let mut closing = false;
         let mut toggle_fullscreen = false;
+        let mut sensor_events = Vec::new();
 
         {
             let window = self.display.get_window().unwrap();
@@ -198,8 +214,12 @@ impl Window {
 
                         if let Some(input) = input {
                             if let ElementState::Released = state {
-                                decoder.process_input(input);
+                                self.pressed_keys.remove(&input);
+                                sensor_events.push(Event::SensorExtended(input));
                             } else {
+                                if self.pressed_keys.insert(input) {
+                                    sensor_events.push(Event::SensorFlexed(input));
+                                }
                                 ignore_next_char = true;
                             }
                             continue;
@@ -218,6 +238,16 @@ impl Window {
             }
         }
 
+        sensor_events.extend(arduino.poll_events());
+        for event in sensor_events {
+            let now = Instant::now();
+            statistics.record_event(&event, now);
+            if let Some(event) = decoder.process_event_at(&event, now) {
+                statistics.record_input_event(&event, now);
+                self.process_input_event(event);
+            }
+        }
+
         if closing {
             Ok(false)
         } else {
@@ -229,7 +259,21 @@ impl Window {
         }
     }
 
-    fn update_ui(&mut self, decoder: &mut Decoder) {
+    fn process_input_event(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Line(line) | InputEvent::Phrase(line) => {
+                self.apps[self.active_app].process_line(&line);
+            }
+            InputEvent::Macro(events) => {
+                for event in events {
+                    self.process_input_event(event);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn update_ui(&mut self, decoder: &mut InputMethod, statistics: &Statistics) {
         let ui = &mut self.ui.set_widgets();
 
         let font_size = title_bar::Style::new().font_size(&ui.theme);
@@ -263,6 +307,42 @@ impl Window {
                  .place_on_kid_area(false)
                  .set(self.widgets.CONTROL_TITLE, ui);
 
+        let session = statistics.session();
+        let mut hints = decoder.status();
+        hints.push(format!("{}: {:.1}", t!("Characters per minute"), session.characters_per_minute()));
+        hints.push(format!("{}: {:.1}", t!("Words per minute"), session.words_per_minute()));
+        hints.push(format!("{}: {:.2}", t!("Flexes per character"), session.flexes_per_character()));
+        hints.push(format!("{}: {:.0}%", t!("Illegal inputs"), session.illegal_rate() * 100.0));
+        hints.push(format!("{}: {:.0}%", t!("Deletions"), session.deletion_rate() * 100.0));
+        hints.push(format!("{}: {:.1}", t!("Seconds per word"), session.seconds_per_word()));
+        let hints = hints.join("\n");
+
+        Text::new(&hints)
+             .kid_area_w_of(self.widgets.CONTROL_CANVAS)
+             .mid_top_of(self.widgets.CONTROL_CANVAS)
+             .set(self.widgets.CONTROL_HINTS, ui);
+
+        if let Some((grid, state)) = decoder.grid() {
+            let columns = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+            let mut cells = Matrix::new(columns, grid.len())
+                                   .kid_area_w_of(self.widgets.CONTROL_CANVAS)
+                                   .h(grid.len() as f64 * 30.0)
+                                   .down_from(self.widgets.CONTROL_HINTS, 10.0)
+                                   .set(self.widgets.SCAN_GRID, ui);
+            while let Some(cell) = cells.next(ui) {
+                let (row, column) = (cell.row, cell.col);
+                if let Some(input) = grid[row].get(column) {
+                    let color = match state {
+                        ScanState::Columns(r, c) if r == row && c == column => color::LIGHT_BLUE,
+                        ScanState::Rows(r) | ScanState::Columns(r, _) if r == row => color::LIGHT_YELLOW,
+                        _ => color::LIGHT_GREY
+                    };
+                    let label = input.to_string();
+                    cell.set(Button::new().color(color).label(&label), ui);
+                }
+            }
+        }
+
         let (mut tab_events, _) = ListSelect::single(self.apps.len(), text::height(1, font_size, 0.0) * 2.0)
                                              .kid_area_wh_of(self.widgets.MODE_CANVAS)
                                              .mid_top_of(self.widgets.MODE_CANVAS)
@@ -298,26 +378,42 @@ impl Window {
                  .place_on_kid_area(false)
                  .set(self.widgets.CONTENT_TITLE, ui);
 
-        let text_events = TextBox::new(&decoder.line())
-                                  .padded_w_of(self.widgets.CONTENT_CANVAS, 10.0)
-                                  .middle_of(self.widgets.CONTENT_CANVAS)
-                                  .color(color::LIGHT_GREY)
-                                  .place_on_kid_area(false)
-                                  .down_from(self.widgets.CONTENT_TITLE, 10.0)
-                                  .set(self.widgets.INPUT_LINE, ui);
-
-        // for event in text_events {
-        //     match event {
-        //         text_box::Event::Update(text) => {
-        //             //self.text = text;
-        //         }
-        //         text_box::Event::Enter if !decoder.line().is_empty() => {
-        //             //self.voice.speak(&self.text).unwrap();
-        //             app.process_line(decoder.line());
-        //         }
-        //         //_ => {}
-        //     }
-        // }
+        // The cursor is drawn between the text before and after it, so that it can't be mistaken for a
+        // character in the line. Questions are shown by highlighting the line, so that the user knows how
+        // it will be spoken.
+        let line = decoder.line();
+        let cursor = line.char_indices().nth(decoder.cursor()).map_or(line.len(), |(index, _)| index);
+        let (before, after) = line.split_at(cursor);
+        let question = decoder.question();
+
+        Rectangle::fill([0.0, title_bar::calc_height(font_size)])
+                  .padded_w_of(self.widgets.CONTENT_CANVAS, 10.0)
+                  .color(if question { color::LIGHT_YELLOW } else { color::LIGHT_GREY })
+                  .down_from(self.widgets.CONTENT_TITLE, 10.0)
+                  .set(self.widgets.INPUT_LINE, ui);
+
+        Text::new(before)
+             .font_size(font_size)
+             .no_line_wrap()
+             .mid_left_with_margin_on(self.widgets.INPUT_LINE, 5.0)
+             .set(self.widgets.INPUT_BEFORE, ui);
+
+        Rectangle::fill([2.0, text::height(1, font_size, 0.0)])
+                  .color(color::BLACK)
+                  .right_from(self.widgets.INPUT_BEFORE, 1.0)
+                  .set(self.widgets.INPUT_CURSOR, ui);
+
+        Text::new(after)
+             .font_size(font_size)
+             .no_line_wrap()
+             .right_from(self.widgets.INPUT_CURSOR, 1.0)
+             .set(self.widgets.INPUT_AFTER, ui);
+
+        if question {
+            Text::new(t!("Question"))
+                 .mid_right_with_margin_on(self.widgets.INPUT_LINE, 10.0)
+                 .set(self.widgets.QUESTION, ui);
+        }
 
         app.update_ui(self.widgets.CONTENT_CANVAS, ui);
     }
@@ -335,9 +431,15 @@ impl Window {
         Ok(())
     }
 
-    pub fn update(&mut self, decoder: &mut Decoder) -> Result<bool> {
-        if self.handle_events(decoder)? {
-            self.update_ui(decoder);
+    pub fn update(&mut self, decoder: &mut InputMethod, arduino: &ArduinoController,
+                  statistics: &mut Statistics) -> Result<bool> {
+        if self.handle_events(decoder, arduino, statistics)? {
+            let now = Instant::now();
+            if let Some(event) = decoder.tick(now) {
+                statistics.record_input_event(&event, now);
+                self.process_input_event(event);
+            }
+            self.update_ui(decoder, statistics);
             self.draw_if_changed()?;
 
             Ok(true)
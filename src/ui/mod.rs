@@ -1,7 +1,7 @@
 use arduino::Port;
 use arduino::thread::ArduinoController;
 use config::Configuration;
-use decoder::Decoder;
+use decoder;
 use error::*;
 
 use std::fmt::Write;
@@ -15,7 +15,7 @@ pub fn run() -> result::Result<(), ()> {
     fn run() -> Result<()> {
         let config = Configuration::new(Path::new("config.toml"))?;
         let arduino = ArduinoController::new(Port::new("COM3"), Vec::new());
-        let decoder = Decoder::new(&config)?;
+        let decoder = decoder::from_config(&config)?;
 
         #[cfg(feature = "conrod")]
         conrod::run(config, arduino, decoder)
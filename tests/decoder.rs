@@ -0,0 +1,804 @@
+extern crate commcomm;
+extern crate flate2;
+extern crate tempdir;
+
+use commcomm::arduino::Event;
+use commcomm::config::Configuration;
+use commcomm::decoder::{self, Decoder, Dictionary, InputEvent, InputMethod};
+use commcomm::decoder::morse::Morse;
+use commcomm::decoder::scan::{Scan, ScanState};
+
+use flate2::{Compression, FlateWriteExt};
+
+use tempdir::TempDir;
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::iter;
+use std::time::{Duration, Instant};
+
+const CONFIRM: usize = 3;
+
+fn decoder(scheme: &str) -> Decoder {
+    decoder_with_dictionary(scheme, None)
+}
+
+fn decoder_with_dictionary(scheme: &str, dictionary: Option<&Dictionary>) -> Decoder {
+    decoder_with_settings(&format!("confirm = {}", CONFIRM), scheme, dictionary)
+}
+
+fn decoder_with_settings(settings: &str, scheme: &str, dictionary: Option<&Dictionary>) -> Decoder {
+    let (_temp_dir, config) = configuration(settings, scheme, dictionary);
+    Decoder::new(&config).unwrap()
+}
+
+fn configuration(settings: &str, scheme: &str, dictionary: Option<&Dictionary>) -> (TempDir, Configuration) {
+    configuration_with_prediction(settings, scheme, "", dictionary)
+}
+
+// The temporary directory holds the dictionary files and must outlive the decoder's construction.
+fn configuration_with_prediction(settings: &str, scheme: &str, prediction: &str,
+                                 dictionary: Option<&Dictionary>) -> (TempDir, Configuration) {
+    let temp_dir = TempDir::new("commcomm-test").unwrap();
+    let config_path = temp_dir.path().join("config.toml");
+    let dictionary = dictionary.map_or(String::new(), |dictionary| {
+        let dictionary_path = temp_dir.path().join("words.dict");
+        dictionary.write_to_file(&dictionary_path).unwrap();
+        format!("dictionary = '{}'", dictionary_path.display())
+    });
+    let config = format!(r#"
+[speech]
+engine = "sapi"
+
+[arduino]
+board = "leonardo"
+port = "COM3"
+
+[[arduino.sensors]]
+pin = "A0"
+label = "Thumb"
+limits = [0, 1023]
+thresholds = [100, 150]
+
+[[arduino.sensors]]
+pin = "A1"
+label = "Index"
+limits = [0, 1023]
+thresholds = [100, 150]
+
+[[arduino.sensors]]
+pin = "A2"
+label = "Middle"
+limits = [0, 1023]
+thresholds = [100, 150]
+
+[[arduino.sensors]]
+pin = "A3"
+label = "Ring"
+limits = [0, 1023]
+thresholds = [100, 150]
+
+[decoder]
+{}
+
+[decoder.scheme]
+{}
+
+[decoder.prediction]
+suggestions = 3
+{}
+{}
+"#, settings, scheme, prediction, dictionary);
+    File::create(&config_path).and_then(|mut file| file.write_all(config.as_bytes())).unwrap();
+
+    let config = Configuration::new(&config_path).unwrap();
+    (temp_dir, config)
+}
+
+fn enter(decoder: &mut Decoder, sequence: &[usize]) -> Option<InputEvent> {
+    for &input in sequence {
+        assert_eq!(decoder.process_input(input), None);
+    }
+    decoder.process_input(CONFIRM)
+}
+
+const SCHEME: &'static str = r#"
+"append:hi" = [0]
+"append:there" = [1]
+"question" = [2]
+"delete" = [0, 1]
+"accept:0" = [1, 0]
+"accept:2" = [1, 1]
+"space" = [2, 0]
+"enter" = [2, 1]
+"delete_word" = [2, 2]
+"delete_line" = [0, 0]
+"undo" = [1, 2]
+"redo" = [1, 2, 2]
+"cursor_left" = [2, 0, 0]
+"cursor_right" = [2, 0, 1]
+"word_left" = [2, 1, 0]
+"word_right" = [2, 1, 1]
+"#;
+
+#[test]
+fn question_toggles() {
+    let mut decoder = decoder(SCHEME);
+    assert!(!decoder.question());
+    assert_eq!(enter(&mut decoder, &[2]), Some(InputEvent::Question(true)));
+    assert!(decoder.question());
+    assert_eq!(enter(&mut decoder, &[2]), Some(InputEvent::Question(false)));
+    assert!(!decoder.question());
+}
+
+#[test]
+fn question_punctuation() {
+    let mut decoder = decoder(SCHEME);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2]);
+    assert_eq!(decoder.commit_line(), Some("Hi?".to_string()));
+    assert!(!decoder.question());
+
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.commit_line(), Some("Hi.".to_string()));
+}
+
+#[test]
+fn question_empty_line() {
+    let mut decoder = decoder(SCHEME);
+    enter(&mut decoder, &[2]);
+    assert_eq!(decoder.commit_line(), None);
+    assert!(!decoder.question());
+}
+
+#[test]
+fn suggestions() {
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("high", 10);
+    dictionary.insert("hill", 30);
+    dictionary.insert("hi-fi", 20);
+    dictionary.insert("there", 40);
+
+    let mut decoder = decoder_with_dictionary(SCHEME, Some(&dictionary));
+    assert!(decoder.suggestions().is_empty());
+    assert!(decoder.status().iter().all(|line| !line.starts_with("Suggestions")));
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.suggestions(), vec!["hill", "hi-fi", "high"]);
+    assert!(decoder.status().contains(&"Suggestions: 0:hill 1:hi-fi 2:high".to_string()));
+    assert_eq!(enter(&mut decoder, &[1, 0]), Some(InputEvent::Completion("Hill".to_string())));
+    assert_eq!(decoder.line(), "Hill");
+}
+
+#[test]
+fn suggestions_missing() {
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("hill", 30);
+
+    let mut decoder = decoder_with_dictionary(SCHEME, Some(&dictionary));
+    enter(&mut decoder, &[0]);
+    assert_eq!(enter(&mut decoder, &[1, 1]), Some(InputEvent::Illegal));
+    assert_eq!(decoder.line(), "Hi");
+}
+
+#[test]
+fn words_and_lines() {
+    let mut decoder = decoder(SCHEME);
+    enter(&mut decoder, &[0]);
+    assert_eq!(enter(&mut decoder, &[2, 0]), Some(InputEvent::Word("Hi".to_string())));
+    enter(&mut decoder, &[1]);
+    assert_eq!(decoder.line(), "Hi there");
+    assert_eq!(enter(&mut decoder, &[2, 1]), Some(InputEvent::Line("Hi there.".to_string())));
+    assert_eq!(decoder.line(), "");
+    assert_eq!(enter(&mut decoder, &[2, 1]), Some(InputEvent::Illegal));
+}
+
+#[test]
+fn delete_words_and_lines() {
+    let mut decoder = decoder(SCHEME);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2, 0]);
+    enter(&mut decoder, &[1]);
+    assert_eq!(enter(&mut decoder, &[2, 2]), Some(InputEvent::DeleteWord));
+    assert_eq!(decoder.line(), "Hi ");
+    assert_eq!(enter(&mut decoder, &[0, 1]), Some(InputEvent::DeleteLetter));
+    assert_eq!(decoder.line(), "Hi");
+    enter(&mut decoder, &[2, 0]);
+    enter(&mut decoder, &[1]);
+    assert_eq!(enter(&mut decoder, &[0, 0]), Some(InputEvent::DeleteLine));
+    assert_eq!(decoder.line(), "");
+    assert_eq!(enter(&mut decoder, &[0, 1]), Some(InputEvent::Illegal));
+}
+
+#[test]
+fn confirm_actions() {
+    let mut decoder = decoder(&format!(r#"{}
+[decoder.confirm_actions]
+2 = "space"
+3 = "enter"
+"#, SCHEME));
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("Hi".to_string())));
+    assert_eq!(decoder.process_input(CONFIRM), Some(InputEvent::Word("Hi".to_string())));
+    assert_eq!(decoder.process_input(CONFIRM), Some(InputEvent::Line("Hi.".to_string())));
+    assert_eq!(decoder.process_input(CONFIRM), None);
+}
+
+#[test]
+fn confirm_timeout() {
+    let mut decoder = decoder_with_settings("confirm_timeout = 500", SCHEME, None);
+    let start = Instant::now();
+    assert_eq!(decoder.process_input_at(0, start), None);
+    assert_eq!(decoder.process_input_at(1, start + Duration::from_millis(400)), None);
+    assert_eq!(decoder.tick(start + Duration::from_millis(800)), None);
+    assert_eq!(decoder.tick(start + Duration::from_millis(900)), Some(InputEvent::Illegal));
+    assert_eq!(decoder.tick(start + Duration::from_millis(2000)), None);
+
+    assert_eq!(decoder.process_input_at(0, start + Duration::from_millis(2000)), None);
+    assert_eq!(decoder.tick(start + Duration::from_millis(2500)), Some(InputEvent::Letters("Hi".to_string())));
+}
+
+#[test]
+fn confirm_timeout_with_sensor() {
+    let mut decoder = decoder_with_settings(&format!("confirm = {}\nconfirm_timeout = 500", CONFIRM), SCHEME, None);
+    let start = Instant::now();
+    assert_eq!(decoder.process_input_at(0, start), None);
+    assert_eq!(decoder.process_input_at(CONFIRM, start + Duration::from_millis(100)),
+               Some(InputEvent::Letters("Hi".to_string())));
+    assert_eq!(decoder.tick(start + Duration::from_millis(1000)), None);
+}
+
+#[test]
+fn chords() {
+    let mut decoder = decoder_with_settings("mode = \"chord\"", r#"
+"append:hi" = [0]
+"append:there" = [2, 1]
+"space" = [1]
+"#, None);
+    assert_eq!(decoder.process_event(&Event::SensorFlexed(0)), None);
+    assert_eq!(decoder.process_event(&Event::SensorExtended(0)), Some(InputEvent::Letters("Hi".to_string())));
+    assert_eq!(decoder.process_event(&Event::SensorFlexed(1)), None);
+    let hints = decoder.hints();
+    assert_eq!(hints.keys().cloned().collect::<Vec<_>>(), vec![2]);
+    assert_eq!(hints[&2].iter().map(|hint| (hint.input.to_string(), hint.remaining)).collect::<Vec<_>>(),
+               vec![("there".to_string(), 1)]);
+    assert_eq!(decoder.process_event(&Event::SensorFlexed(2)), None);
+    assert_eq!(decoder.process_event(&Event::SensorExtended(1)), None);
+    assert_eq!(decoder.process_event(&Event::SensorExtended(2)), Some(InputEvent::Letters("there".to_string())));
+    assert_eq!(decoder.process_event(&Event::SensorFlexed(0)), None);
+    assert_eq!(decoder.process_event(&Event::SensorFlexed(2)), None);
+    assert_eq!(decoder.process_event(&Event::SensorExtended(0)), None);
+    assert_eq!(decoder.process_event(&Event::SensorExtended(2)), Some(InputEvent::Illegal));
+    assert_eq!(decoder.line(), "Hithere");
+}
+
+#[test]
+fn undo_redo() {
+    let mut decoder = decoder(SCHEME);
+    assert_eq!(enter(&mut decoder, &[1, 2]), Some(InputEvent::Illegal));
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2, 0]);
+    enter(&mut decoder, &[1]);
+    assert_eq!(enter(&mut decoder, &[0, 0]), Some(InputEvent::DeleteLine));
+    assert_eq!(decoder.line(), "");
+    assert_eq!(enter(&mut decoder, &[1, 2]), Some(InputEvent::Undo));
+    assert_eq!(decoder.line(), "Hi there");
+    assert_eq!(enter(&mut decoder, &[1, 2]), Some(InputEvent::Undo));
+    assert_eq!(decoder.line(), "Hi ");
+    assert_eq!(enter(&mut decoder, &[1, 2, 2]), Some(InputEvent::Redo));
+    assert_eq!(decoder.line(), "Hi there");
+    enter(&mut decoder, &[0, 1]);
+    assert_eq!(enter(&mut decoder, &[1, 2, 2]), Some(InputEvent::Illegal));
+    assert_eq!(decoder.line(), "Hi ");
+}
+
+#[test]
+fn undo_history() {
+    let mut decoder = decoder(SCHEME);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2, 2]);
+    assert_eq!(enter(&mut decoder, &[0, 1]), Some(InputEvent::Illegal));
+    assert_eq!(enter(&mut decoder, &[2, 0]), Some(InputEvent::Illegal));
+    assert_eq!(enter(&mut decoder, &[1, 2]), Some(InputEvent::Undo));
+    assert_eq!(decoder.line(), "Hi");
+
+    for _ in 0..150 {
+        enter(&mut decoder, &[1]);
+    }
+    let mut undone = 0;
+    while enter(&mut decoder, &[1, 2]) == Some(InputEvent::Undo) {
+        undone += 1;
+    }
+    assert_eq!(undone, 100);
+    assert_eq!(decoder.line(), format!("Hi{}", iter::repeat("there").take(50).collect::<String>()));
+}
+
+#[test]
+fn cursor_editing() {
+    let mut decoder = decoder(SCHEME);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2, 0]);
+    enter(&mut decoder, &[1]);
+    enter(&mut decoder, &[2, 0]);
+    enter(&mut decoder, &[1]);
+    assert_eq!(decoder.line(), "Hi there there");
+    assert_eq!(decoder.cursor(), 14);
+
+    assert_eq!(enter(&mut decoder, &[2, 1, 0]), Some(InputEvent::Cursor(9)));
+    assert_eq!(enter(&mut decoder, &[2, 0, 0]), Some(InputEvent::Cursor(8)));
+    assert_eq!(enter(&mut decoder, &[2, 0, 1]), Some(InputEvent::Cursor(9)));
+    assert_eq!(enter(&mut decoder, &[0, 1]), Some(InputEvent::DeleteLetter));
+    assert_eq!(decoder.line(), "Hi therethere");
+    assert_eq!(decoder.cursor(), 8);
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.line(), "Hi therehithere");
+    assert_eq!(decoder.cursor(), 10);
+
+    assert_eq!(enter(&mut decoder, &[2, 1, 1]), Some(InputEvent::Cursor(15)));
+    assert_eq!(enter(&mut decoder, &[2, 0, 1]), Some(InputEvent::Illegal));
+    assert_eq!(enter(&mut decoder, &[2, 1, 0]), Some(InputEvent::Cursor(3)));
+    assert_eq!(enter(&mut decoder, &[2, 1, 0]), Some(InputEvent::Cursor(0)));
+    assert_eq!(enter(&mut decoder, &[2, 0, 0]), Some(InputEvent::Illegal));
+    assert_eq!(decoder.line(), "Hi therehithere");
+
+    enter(&mut decoder, &[2, 0, 1]);
+    enter(&mut decoder, &[2, 0, 1]);
+    assert_eq!(enter(&mut decoder, &[2, 0, 1]), Some(InputEvent::Cursor(8)));
+    assert_eq!(enter(&mut decoder, &[2, 2]), Some(InputEvent::DeleteWord));
+    assert_eq!(decoder.line(), "Hi ");
+    assert_eq!(decoder.cursor(), 3);
+}
+
+const PUNCTUATION_SCHEME: &'static str = r#"
+"append:hi" = [0]
+"append:." = [1]
+"append:," = [1, 1]
+"space" = [2]
+"shift" = [0, 0]
+"caps_lock" = [0, 1]
+"#;
+
+#[test]
+fn sentence_capitalization() {
+    let mut decoder = decoder(PUNCTUATION_SCHEME);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[1, 1]);
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("hi".to_string())));
+    enter(&mut decoder, &[1]);
+    assert_eq!(decoder.line(), "Hi, hi. ");
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("Hi".to_string())));
+    assert_eq!(decoder.commit_line(), Some("Hi, hi. Hi.".to_string()));
+
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[1, 1]);
+    assert_eq!(decoder.commit_line(), Some("Hi.".to_string()));
+}
+
+#[test]
+fn shift_and_caps_lock() {
+    let mut decoder = decoder(PUNCTUATION_SCHEME);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2]);
+    assert_eq!(enter(&mut decoder, &[0, 0]), Some(InputEvent::Shift(true)));
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("Hi".to_string())));
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("hi".to_string())));
+    assert_eq!(enter(&mut decoder, &[0, 1]), Some(InputEvent::CapsLock(true)));
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("HI".to_string())));
+    assert_eq!(decoder.line(), "Hi HihiHI");
+}
+
+#[test]
+fn brackets_and_quotes() {
+    let mut decoder = decoder(r#"
+"append:hi" = [0]
+"append:." = [1]
+"append:(" = [2]
+"append:)" = [0, 0]
+"append:\"" = [0, 1]
+"#);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2]);
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("hi".to_string())));
+    enter(&mut decoder, &[0, 0]);
+    enter(&mut decoder, &[1]);
+    assert_eq!(decoder.line(), "Hi (hi). ");
+    enter(&mut decoder, &[0, 1]);
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("Hi".to_string())));
+    enter(&mut decoder, &[1]);
+    enter(&mut decoder, &[0, 1]);
+    assert_eq!(decoder.line(), "Hi (hi). \"Hi.\" ");
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("Hi".to_string())));
+    assert_eq!(decoder.commit_line(), Some("Hi (hi). \"Hi.\" Hi.".to_string()));
+}
+
+#[test]
+fn formatting_disabled() {
+    let mut decoder = decoder_with_settings(&format!(r#"confirm = {}
+[decoder.formatting]
+capitalize = false
+punctuation_spacing = false
+"#, CONFIRM), PUNCTUATION_SCHEME, None);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2]);
+    enter(&mut decoder, &[1]);
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.line(), "hi .hi");
+}
+
+#[test]
+fn hints() {
+    let mut decoder = decoder(SCHEME);
+    assert_eq!(decoder.process_input(2), None);
+    let hints = decoder.hints();
+    assert_eq!(hints.keys().cloned().collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(hints[&0].iter().map(|hint| (hint.input.to_string(), hint.remaining)).collect::<Vec<_>>(),
+               vec![("space".to_string(), 1), ("cursor_left".to_string(), 2), ("cursor_right".to_string(), 2)]);
+    assert_eq!(hints[&2].iter().map(|hint| (hint.input.to_string(), hint.remaining)).collect::<Vec<_>>(),
+               vec![("delete_word".to_string(), 1)]);
+}
+
+#[test]
+fn phrases_and_macros() {
+    let mut decoder = decoder(r#"
+"append:hi" = [0]
+"phrase: I'm thirsty. " = [1]
+"macro: space; append:hi; enter" = [2]
+"undo" = [0, 0]
+"#);
+    assert_eq!(enter(&mut decoder, &[1]), Some(InputEvent::Phrase("I'm thirsty.".to_string())));
+    enter(&mut decoder, &[0]);
+    assert_eq!(enter(&mut decoder, &[2]), Some(InputEvent::Macro(vec![
+        InputEvent::Word("Hi".to_string()),
+        InputEvent::Letters("hi".to_string()),
+        InputEvent::Line("Hi hi.".to_string())
+    ])));
+    assert_eq!(enter(&mut decoder, &[0, 0]), Some(InputEvent::Undo));
+    assert_eq!(decoder.line(), "Hi");
+}
+
+#[test]
+fn layers() {
+    let mut decoder = decoder(r#"
+"append:hi" = [0]
+"layer:digits" = [1]
+"layer_once:digits" = [2]
+
+[decoder.layers.digits]
+"append:1" = [0]
+"layer:default" = [1]
+"#);
+    assert_eq!(decoder.layer(), "default");
+    assert_eq!(enter(&mut decoder, &[2]), Some(InputEvent::Layer("digits".to_string())));
+    assert_eq!(enter(&mut decoder, &[0]), Some(InputEvent::Letters("1".to_string())));
+    assert_eq!(decoder.layer(), "default");
+    assert_eq!(enter(&mut decoder, &[1]), Some(InputEvent::Layer("digits".to_string())));
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.layer(), "digits");
+    assert_eq!(enter(&mut decoder, &[1]), Some(InputEvent::Layer("default".to_string())));
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.line(), "111hi");
+}
+
+#[test]
+fn one_shot_layers() {
+    let mut decoder = decoder(r#"
+"append:hi" = [0]
+"layer_once:digits" = [2]
+
+[decoder.layers.digits]
+"append:1" = [0]
+"layer_once:letters" = [2]
+
+[decoder.layers.letters]
+"append:x" = [0]
+
+[decoder.confirm_actions]
+2 = "space"
+"#);
+    enter(&mut decoder, &[2]);
+    enter(&mut decoder, &[2]);
+    assert_eq!(decoder.layer(), "letters");
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.layer(), "default");
+
+    enter(&mut decoder, &[2]);
+    assert_eq!(decoder.layer(), "digits");
+    assert_eq!(decoder.process_input(CONFIRM), Some(InputEvent::Word("X".to_string())));
+    assert_eq!(decoder.layer(), "default");
+}
+
+#[test]
+fn macro_escapes_and_layers() {
+    let mut decoder = decoder(r#"
+"macro: phrase: a\\;b\\\\c" = [1]
+"layer_once:digits" = [2]
+
+[decoder.layers.digits]
+"append:1" = [0]
+"macro: append:1; layer:digits" = [1]
+"#);
+    assert_eq!(enter(&mut decoder, &[1]), Some(InputEvent::Macro(vec![InputEvent::Phrase("a;b\\c".to_string())])));
+    enter(&mut decoder, &[2]);
+    assert_eq!(enter(&mut decoder, &[1]), Some(InputEvent::Macro(vec![
+        InputEvent::Letters("1".to_string()),
+        InputEvent::Layer("digits".to_string())
+    ])));
+    assert_eq!(decoder.layer(), "digits");
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.layer(), "digits");
+}
+
+#[test]
+fn learning() {
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("hill", 30);
+    dictionary.insert("hello", 10);
+
+    let (temp_dir, config) = configuration(&format!("confirm = {}", CONFIRM), SCHEME, Some(&dictionary));
+    let mut decoder = Decoder::new(&config).unwrap();
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2, 0]);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[1]);
+    enter(&mut decoder, &[2, 0]);
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.suggestions(), vec!["hill", "hi", "hithere"]);
+
+    // Learned words are saved when the decoder goes away.
+    let user_dictionary = temp_dir.path().join("words.user.dict");
+    assert!(!user_dictionary.exists());
+    drop(decoder);
+    let learned = Dictionary::from_file(&user_dictionary).unwrap();
+    assert_eq!(learned.lookup("hi"), vec![("hi", 1)]);
+    assert_eq!(learned.lookup("hithere"), vec![("hithere", 1)]);
+}
+
+#[test]
+fn learning_before_punctuation() {
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("hill", 30);
+
+    let (temp_dir, config) = configuration(&format!("confirm = {}", CONFIRM), r#"
+"append:hello" = [0]
+"append:." = [1]
+"space" = [2]
+"#, Some(&dictionary));
+    let mut decoder = Decoder::new(&config).unwrap();
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[1]);
+    assert_eq!(decoder.line(), "Hello.");
+    drop(decoder);
+
+    let learned = Dictionary::from_file(&temp_dir.path().join("words.user.dict")).unwrap();
+    assert_eq!(learned.lookup("hello"), vec![("hello", 1)]);
+}
+
+#[test]
+fn next_word_prediction() {
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("hi", 10);
+    dictionary.insert("there", 10);
+    dictionary.insert("hill", 10);
+    dictionary.add_ngram(&["hi"], "there", 3);
+    dictionary.add_ngram(&["hi"], "hill", 1);
+    dictionary.add_ngram(&["hi", "hi"], "hill", 2);
+
+    let mut decoder = decoder_with_dictionary(SCHEME, Some(&dictionary));
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.suggestions(), vec!["hi", "hill"]);
+    enter(&mut decoder, &[2, 0]);
+    assert_eq!(decoder.suggestions(), vec!["there", "hill"]);
+    enter(&mut decoder, &[0]);
+    enter(&mut decoder, &[2, 0]);
+    assert_eq!(decoder.suggestions(), vec!["hill", "hi", "there"]);
+    assert_eq!(enter(&mut decoder, &[1, 0]), Some(InputEvent::Completion("hill".to_string())));
+    assert_eq!(decoder.line(), "Hi hi hill");
+}
+
+const FUZZY_SCHEME: &'static str = r#"
+"append:a" = [0, 0]
+"append:b" = [0, 1]
+"append:c" = [1, 0]
+"space" = [2]
+"correct:0" = [1, 1]
+"correct:1" = [1, 2]
+"#;
+
+#[test]
+fn fuzzy_auto_correction() {
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("ab", 10);
+    dictionary.insert("ac", 1);
+
+    let settings = format!("confirm = {}\n[decoder.fuzzy]\ncorrection = 'auto'", CONFIRM);
+    let mut decoder = decoder_with_settings(&settings, FUZZY_SCHEME, Some(&dictionary));
+    enter(&mut decoder, &[0, 0]);
+    assert_eq!(enter(&mut decoder, &[0, 1, 1]), Some(InputEvent::Letters("b".to_string())));
+    assert_eq!(enter(&mut decoder, &[0, 1, 1, 1]), Some(InputEvent::Illegal));
+    assert_eq!(decoder.line(), "Ab");
+}
+
+#[test]
+fn fuzzy_suggestions() {
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("ab", 10);
+    dictionary.insert("ac", 1);
+
+    let settings = format!("confirm = {}\n[decoder.fuzzy]\ncorrection = 'suggest'", CONFIRM);
+    let mut decoder = decoder_with_settings(&settings, FUZZY_SCHEME, Some(&dictionary));
+    enter(&mut decoder, &[0, 0]);
+    let corrections = vec!["b".to_string(), "c".to_string()];
+    assert_eq!(enter(&mut decoder, &[1]), Some(InputEvent::Corrections(corrections)));
+    assert_eq!(decoder.corrections().len(), 2);
+    assert_eq!(enter(&mut decoder, &[1, 2]), Some(InputEvent::Letters("c".to_string())));
+    assert!(decoder.corrections().is_empty());
+    assert_eq!(enter(&mut decoder, &[1, 2]), Some(InputEvent::Illegal));
+    assert_eq!(decoder.line(), "Ac");
+}
+
+#[test]
+fn fuzzy_commands() {
+    let settings = format!("confirm = {}\n[decoder.fuzzy]\ncorrection = 'suggest'", CONFIRM);
+    let mut decoder = decoder_with_settings(&settings, r#"
+"append:a" = [0, 0]
+"correct:0" = [1, 1]
+"#, None);
+    assert_eq!(enter(&mut decoder, &[1]), Some(InputEvent::Illegal));
+    assert_eq!(enter(&mut decoder, &[1, 1]), Some(InputEvent::Illegal));
+    assert_eq!(decoder.line(), "");
+}
+
+fn key(decoder: &mut InputMethod, start: Instant, from: u64, to: u64) -> Option<InputEvent> {
+    let event = decoder.process_event_at(&Event::SensorFlexed(0), start + Duration::from_millis(from));
+    assert_eq!(decoder.process_event_at(&Event::SensorExtended(0), start + Duration::from_millis(to)), None);
+    event
+}
+
+#[test]
+fn morse() {
+    let settings = "method = \"morse\"\n[decoder.morse]\nunit = 100\nadaptive = false";
+    let (_temp_dir, config) = configuration(settings, "", None);
+    let mut decoder = Morse::new(&config).unwrap();
+    let start = Instant::now();
+    assert_eq!(key(&mut decoder, start, 0, 100), None);
+    assert_eq!(key(&mut decoder, start, 200, 300), None);
+    assert_eq!(key(&mut decoder, start, 400, 500), None);
+    assert_eq!(key(&mut decoder, start, 600, 700), None);
+    assert_eq!(key(&mut decoder, start, 1000, 1100), Some(InputEvent::Letters("H".to_string())));
+    assert_eq!(decoder.code(), ".");
+    assert_eq!(key(&mut decoder, start, 1200, 1300), None);
+    assert_eq!(decoder.tick(start + Duration::from_millis(1600)), Some(InputEvent::Letters("i".to_string())));
+    assert_eq!(decoder.tick(start + Duration::from_millis(1900)), Some(InputEvent::Word("Hi".to_string())));
+    assert_eq!(key(&mut decoder, start, 2500, 2800), None);
+    assert_eq!(key(&mut decoder, start, 3900, 4000), Some(InputEvent::Macro(vec![
+        InputEvent::Letters("t".to_string()),
+        InputEvent::Word("t".to_string())
+    ])));
+    assert_eq!(decoder.tick(start + Duration::from_millis(4300)), Some(InputEvent::Letters("e".to_string())));
+    assert_eq!(decoder.line(), "Hi t e");
+}
+
+#[test]
+fn morse_adaptation() {
+    let settings = "method = \"morse\"\n[decoder.morse]\nunit = 100\nadaptive = true";
+    let (_temp_dir, config) = configuration(settings, "", None);
+    let mut decoder = Morse::new(&config).unwrap();
+    let start = Instant::now();
+    assert_eq!(key(&mut decoder, start, 0, 300), None);
+    assert_eq!(decoder.unit(), Duration::from_millis(100));
+
+    // A press that is held far too long only counts as twice the expected length.
+    assert_eq!(key(&mut decoder, start, 400, 3400), None);
+    assert_eq!(decoder.unit(), Duration::from_millis(120));
+    assert_eq!(key(&mut decoder, start, 3500, 3600), None);
+    assert_eq!(decoder.code(), "--.");
+}
+
+#[test]
+fn scanning() {
+    let settings = "method = \"scan\"\n[decoder.scan]\ninterval = 100\ngrid = [[\"append:a\", \"append:b\"], [\"space\", \"delete\"]]";
+    let (_temp_dir, config) = configuration(settings, "", None);
+    let mut decoder = Scan::new(&config).unwrap();
+    let start = Instant::now();
+    let at = |millis| start + Duration::from_millis(millis);
+    assert_eq!(decoder.state(), ScanState::Idle);
+    assert_eq!(decoder.process_event_at(&Event::SensorFlexed(0), at(0)), None);
+    assert_eq!(decoder.process_event_at(&Event::SensorExtended(0), at(10)), None);
+    assert_eq!(decoder.state(), ScanState::Rows(0));
+    assert_eq!(decoder.tick(at(150)), None);
+    assert_eq!(decoder.state(), ScanState::Rows(1));
+    assert_eq!(decoder.tick(at(250)), None);
+    assert_eq!(decoder.state(), ScanState::Rows(0));
+    assert_eq!(decoder.process_event_at(&Event::SensorFlexed(0), at(260)), None);
+    assert_eq!(decoder.state(), ScanState::Columns(0, 0));
+    assert_eq!(decoder.tick(at(370)), None);
+    assert_eq!(decoder.process_event_at(&Event::SensorFlexed(0), at(400)),
+               Some(InputEvent::Letters("B".to_string())));
+    assert_eq!(decoder.state(), ScanState::Rows(0));
+    assert_eq!(decoder.line(), "B");
+}
+
+#[test]
+fn input_methods() {
+    let settings = format!("confirm = {}", CONFIRM);
+    assert!(decoder::from_config(&configuration(&settings, SCHEME, None).1).is_ok());
+    assert!(decoder::from_config(&configuration("method = \"morse\"", "", None).1).is_ok());
+    assert!(decoder::from_config(&configuration("method = \"scan\"", "", None).1).is_ok());
+    assert!(decoder::from_config(&configuration("method = \"typing\"", "", None).1).is_err());
+
+    // The old mode values still select the input method, but not next to another method.
+    assert!(decoder::from_config(&configuration("mode = \"morse\"", "", None).1).is_ok());
+    let settings = format!("confirm = {}\nmethod = \"scheme\"\nmode = \"scan\"", CONFIRM);
+    assert!(decoder::from_config(&configuration(&settings, SCHEME, None).1).is_err());
+
+    // Custom Morse codes can replace built-in ones, but not each other.
+    let settings = "method = \"morse\"\n[decoder.morse.codes]\n\"space\" = \"..--\"\n\"delete\" = \".-\"";
+    assert!(decoder::from_config(&configuration(settings, "", None).1).is_ok());
+    let settings = "method = \"morse\"\n[decoder.morse.codes]\n\"space\" = \"..--\"\n\"delete\" = \"..--\"";
+    assert!(decoder::from_config(&configuration(settings, "", None).1).is_err());
+
+    // Morse code and scanning can not switch layers or pick corrections.
+    let settings = "method = \"morse\"\n[decoder.morse.codes]\n\"layer:default\" = \"..--\"";
+    assert!(decoder::from_config(&configuration(settings, "", None).1).is_err());
+    let settings = "method = \"scan\"\n[decoder.scan]\ngrid = [[\"append:a\", \"macro: space; correct:0\"]]";
+    assert!(decoder::from_config(&configuration(settings, "", None).1).is_err());
+}
+
+#[test]
+fn dictionary_language() {
+    let settings = format!("confirm = {}", CONFIRM);
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("hill", 30);
+    dictionary.set_language(Some("nl-NL"));
+
+    for &(language, valid) in &[("", true), ("language = 'nl'", true), ("language = 'NL-be'", true),
+                                ("language = 'en-US'", false)] {
+        let (_temp_dir, config) = configuration_with_prediction(&settings, SCHEME, language, Some(&dictionary));
+        assert_eq!(Decoder::new(&config).is_ok(), valid);
+    }
+}
+
+#[test]
+fn json_dictionary_language() {
+    let temp_dir = TempDir::new("commcomm-test").unwrap();
+    let path = temp_dir.path().join("words.dict");
+    let json = r#"{"words": {"straße": [[5, "Straße"]]}, "ngrams": {"straße": [[3, "Nummer"]]}}"#;
+    File::create(&path).and_then(|file| {
+        let mut writer = file.zlib_encode(Compression::Best);
+        writer.write_all(json.as_bytes())?;
+        writer.finish().map(|_| ())
+    }).unwrap();
+
+    // Dictionaries without a header are keyed with the rules of the configured language.
+    let settings = format!("confirm = {}", CONFIRM);
+    let prediction = format!("language = 'de'\ndictionary = '{}'", path.display());
+    let (_config_dir, config) = configuration_with_prediction(&settings, r#"
+"append:strasse" = [0]
+"space" = [1]
+"#, &prediction, None);
+    let mut decoder = Decoder::new(&config).unwrap();
+    enter(&mut decoder, &[0]);
+    assert_eq!(decoder.suggestions(), vec!["Straße"]);
+    enter(&mut decoder, &[1]);
+    assert_eq!(decoder.suggestions(), vec!["Nummer"]);
+}
+
+#[test]
+fn dictionary_normalization_version() {
+    let settings = format!("confirm = {}", CONFIRM);
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("hill", 30);
+    let (temp_dir, config) = configuration(&settings, SCHEME, Some(&dictionary));
+    assert!(Decoder::new(&config).is_ok());
+
+    // Pretend the dictionary was built with another version of the normalization rules.
+    let path = temp_dir.path().join("words.dict");
+    let mut bytes = Vec::new();
+    File::open(&path).and_then(|mut file| file.read_to_end(&mut bytes)).unwrap();
+    let current = format!("\"normalization_version\":{}", decoder::normalize::VERSION).into_bytes();
+    let other = format!("\"normalization_version\":{}", decoder::normalize::VERSION + 1).into_bytes();
+    assert_eq!(current.len(), other.len());
+    let start = bytes.windows(current.len()).position(|window| window == &current[..]).unwrap();
+    bytes[start..start + other.len()].copy_from_slice(&other);
+    File::create(&path).and_then(|mut file| file.write_all(&bytes)).unwrap();
+    assert!(Decoder::new(&config).is_err());
+}
@@ -0,0 +1,132 @@
+extern crate commcomm;
+extern crate flate2;
+extern crate tempdir;
+
+use commcomm::decoder::{Dictionary, FORMAT_VERSION};
+use commcomm::decoder::normalize::{self, Normalizer};
+
+use flate2::{Compression, FlateWriteExt};
+
+use tempdir::TempDir;
+
+use std::collections::Bound;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+
+fn dictionary() -> Dictionary {
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("high", 10);
+    dictionary.insert("hill", 30);
+    dictionary.insert("hi-fi", 20);
+    dictionary.insert("hifi", 5);
+    dictionary.insert("there", 40);
+    dictionary.add_ngram(&["hi"], "there", 3);
+    dictionary
+}
+
+#[test]
+fn compact_format() {
+    let temp_dir = TempDir::new("commcomm-test").unwrap();
+    let path = temp_dir.path().join("words.dict");
+    dictionary().write_to_file(&path).unwrap();
+
+    let dictionary = Dictionary::from_file(&path).unwrap();
+    assert_eq!(dictionary.lookup("HiFi"), vec![("hi-fi", 20), ("hifi", 5)]);
+    assert!(dictionary.lookup("ho").is_empty());
+    assert_eq!(dictionary.prefix("hi"), vec![("hi-fi", 20), ("hifi", 5), ("high", 10), ("hill", 30)]);
+    assert_eq!(dictionary.complete("hi", 2), vec!["hill", "hi-fi"]);
+    assert_eq!(dictionary.iter_prefix("hil").collect::<Vec<_>>(), vec![("hill", 30)]);
+    assert_eq!(dictionary.range(Bound::Excluded("high"), Bound::Included("there")).collect::<Vec<_>>(),
+               vec![("hill", 30), ("there", 40)]);
+    assert_eq!(dictionary.total(), 105);
+    assert_eq!(dictionary.predict(&["Hi"]), vec![("there", 1.0)]);
+
+    let mut dictionary = dictionary;
+    dictionary.add("hill", 2);
+    dictionary.write_to_file(&path).unwrap();
+    assert_eq!(Dictionary::from_file(&path).unwrap().lookup("hill"), vec![("hill", 32)]);
+}
+
+#[test]
+fn completions() {
+    let mut dictionary = dictionary();
+    dictionary.insert("hike", 10);
+    assert_eq!(dictionary.completions("HI"),
+               vec![("hill", 30), ("hi-fi", 20), ("high", 10), ("hike", 10), ("hifi", 5)]);
+    assert_eq!(dictionary.complete("hi", 4), vec!["hill", "hi-fi", "high", "hike"]);
+}
+
+#[test]
+fn json_format() {
+    let temp_dir = TempDir::new("commcomm-test").unwrap();
+    for (name, json) in vec![
+        ("plain.dict", r#"{"hill": [[30, "hill"]], "there": [[40, "there"]]}"#),
+        ("ngrams.dict", r#"{"words": {"hill": [[30, "hill"]]}, "ngrams": {"hi": [[3, "there"]]}}"#),
+        // Keys made with older rules can file a word twice, which merges into one entry.
+        ("old_keys.dict", r#"{"Hill": [[10, "hill"]], "hill": [[20, "hill"]]}"#)
+    ] {
+        let path = temp_dir.path().join(name);
+        File::create(&path).and_then(|file| {
+            let mut writer = file.zlib_encode(Compression::Best);
+            writer.write_all(json.as_bytes())?;
+            writer.finish().map(|_| ())
+        }).unwrap();
+
+        let dictionary = Dictionary::from_file(&path).unwrap();
+        assert_eq!(dictionary.lookup("hill"), vec![("hill", 30)]);
+    }
+
+    let dictionary = Dictionary::from_file(&temp_dir.path().join("ngrams.dict")).unwrap();
+    assert_eq!(dictionary.predict(&["hi"]), vec![("there", 1.0)]);
+}
+
+#[test]
+fn truncated_file() {
+    let temp_dir = TempDir::new("commcomm-test").unwrap();
+    let path = temp_dir.path().join("words.dict");
+    dictionary().write_to_file(&path).unwrap();
+    let length = path.metadata().unwrap().len();
+    OpenOptions::new().write(true).open(&path).and_then(|file| file.set_len(length - 10)).unwrap();
+    assert!(Dictionary::from_file(&path).is_err());
+}
+
+#[test]
+fn header() {
+    let temp_dir = TempDir::new("commcomm-test").unwrap();
+    let path = temp_dir.path().join("words.dict");
+    let mut dictionary = dictionary();
+    dictionary.set_language(Some("nl-NL"));
+    dictionary.set_source(Some("test list"));
+    dictionary.write_to_file(&path).unwrap();
+
+    let dictionary = Dictionary::from_file(&path).unwrap();
+    let header = dictionary.header();
+    assert_eq!(header.format_version, FORMAT_VERSION);
+    assert_eq!(header.language, Some("nl-NL".to_string()));
+    assert_eq!(header.normalization, "nl");
+    assert_eq!(header.normalization_version, normalize::VERSION);
+    assert_eq!(header.word_count, 5);
+    assert_eq!(header.source, Some("test list".to_string()));
+    assert!(header.built.is_some());
+
+    let mut bytes = Vec::new();
+    File::open(&path).and_then(|mut file| file.read_to_end(&mut bytes)).unwrap();
+    bytes[7] = FORMAT_VERSION + 1;
+    File::create(&path).and_then(|mut file| file.write_all(&bytes)).unwrap();
+    assert!(Dictionary::from_file(&path).is_err());
+}
+
+#[test]
+fn groups() {
+    let temp_dir = TempDir::new("commcomm-test").unwrap();
+    let path = temp_dir.path().join("words.dict");
+    dictionary().write_to_file(&path).unwrap();
+
+    let dictionary = Dictionary::from_file(&path).unwrap();
+    let keys = dictionary.keys()
+                         .map(|(key, entries)| (key.into_owned(), entries.count()))
+                         .collect::<Vec<_>>();
+    assert_eq!(keys, vec![("hifi".to_string(), 2), ("high".to_string(), 1), ("hill".to_string(), 1),
+                          ("there".to_string(), 1)]);
+    assert_eq!(dictionary.ngrams().flat_map(|(_, entries)| entries).collect::<Vec<_>>(), vec![("there", 3)]);
+}
@@ -0,0 +1,106 @@
+extern crate commcomm;
+
+use commcomm::decoder::Dictionary;
+use commcomm::decoder::normalize::Normalizer;
+
+use std::char;
+
+// Every letter in the Latin-1 Supplement and Latin Extended-A blocks, grouped by its key.
+const LETTERS: &'static [(&'static str, &'static str)] = &[
+    ("ÀÁÂÃÄÅàáâãäåĀāĂăĄą", "a"),
+    ("Ææ", "ae"),
+    ("ÇçĆćĈĉĊċČč", "c"),
+    ("ÐðĎďĐđ", "d"),
+    ("ÈÉÊËèéêëĒēĔĕĖėĘęĚě", "e"),
+    ("ĜĝĞğĠġĢģ", "g"),
+    ("ĤĥĦħ", "h"),
+    ("ÌÍÎÏìíîïĨĩĪīĬĭĮįİı", "i"),
+    ("Ĳĳ", "ij"),
+    ("Ĵĵ", "j"),
+    ("Ķķĸ", "k"),
+    ("ĹĺĻļĽľĿŀŁł", "l"),
+    ("ÑñŃńŅņŇňŉŊŋ", "n"),
+    ("ÒÓÔÕÖØòóôõöøŌōŎŏŐő", "o"),
+    ("Œœ", "oe"),
+    ("ŔŕŖŗŘř", "r"),
+    ("ŚśŜŝŞşŠšſ", "s"),
+    ("ŢţŤťŦŧ", "t"),
+    ("Þþ", "th"),
+    ("ÙÚÛÜùúûüŨũŪūŬŭŮůŰűŲų", "u"),
+    ("Ŵŵ", "w"),
+    ("ÝýÿŶŷŸ", "y"),
+    ("ŹźŻżŽž", "z")
+];
+
+fn normalizers() -> Vec<Normalizer> {
+    vec![Normalizer::new(None), Normalizer::new(Some("en-US")), Normalizer::new(Some("nl-NL"))]
+}
+
+#[test]
+fn latin_letters() {
+    for c in (0xc0..0x180).filter_map(char::from_u32).filter(|&c| c != '×' && c != '÷' && c != 'ß') {
+        assert!(LETTERS.iter().any(|&(letters, _)| letters.contains(c)), "{} is not covered", c);
+    }
+
+    for normalizer in normalizers() {
+        for &(letters, key) in LETTERS {
+            for letter in letters.chars() {
+                assert_eq!(normalizer.key(&letter.to_string()), key, "{} ({})", letter, normalizer.rules());
+            }
+        }
+    }
+}
+
+#[test]
+fn ascii() {
+    for normalizer in normalizers() {
+        assert_eq!(normalizer.key("Hello"), "hello");
+        assert_eq!(normalizer.key("hi-fi"), "hifi");
+        assert_eq!(normalizer.key("don't"), "dont");
+        assert_eq!(normalizer.key("don’t"), "dont");
+        assert_eq!(normalizer.key("e.g./i_e + x"), "egiex");
+        assert_eq!(normalizer.key("CO₂"), "co2");
+        assert_eq!(normalizer.key("ﬁne ﬂow"), "fineflow");
+    }
+}
+
+#[test]
+fn languages() {
+    let english = Normalizer::new(Some("en-US"));
+    let dutch = Normalizer::new(Some("nl-NL"));
+    let german = Normalizer::new(Some("de"));
+    assert_eq!(english.rules(), "default");
+    assert_eq!(dutch.rules(), "nl");
+    assert_eq!(Normalizer::new(Some("NL_be")).rules(), "nl");
+    assert_eq!(german.rules(), "de");
+
+    assert_eq!(dutch.key("Ĳsje"), "ijsje");
+    assert_eq!(dutch.key("IJsje"), "ijsje");
+    assert_eq!(dutch.key("geïnd"), "geind");
+    assert_eq!(english.key("Straße"), "straße");
+    assert_eq!(dutch.key("Straße"), "straße");
+    assert_eq!(german.key("Straße"), "strasse");
+    assert_eq!(german.key("STRAẞE"), "strasse");
+    assert_eq!(german.key("Müller"), "muller");
+}
+
+#[test]
+fn dictionary_keys() {
+    let mut dictionary = Dictionary::new();
+    dictionary.insert("ĳsje", 10);
+    dictionary.insert("Straße", 5);
+    dictionary.set_language(Some("nl-NL"));
+    assert_eq!(dictionary.key("IJSJE"), "ijsje");
+    assert_eq!(dictionary.lookup("ijsje"), vec![("ĳsje", 10)]);
+    assert_eq!(dictionary.prefix("Ĳs"), vec![("ĳsje", 10)]);
+    assert!(dictionary.lookup("strasse").is_empty());
+    dictionary.add_ngram(&["Straße"], "ĳsje", 1);
+    assert!(dictionary.predict(&["strasse"]).is_empty());
+
+    dictionary.set_language(Some("de-DE"));
+    assert_eq!(dictionary.lookup("strasse"), vec![("Straße", 5)]);
+    assert_eq!(dictionary.lookup("STRASSE"), vec![("Straße", 5)]);
+    assert_eq!(dictionary.predict(&["strasse"]), vec![("ĳsje", 1.0)]);
+    dictionary.add_ngram(&["Müller"], "Straße", 2);
+    assert_eq!(dictionary.predict(&["MULLER"]), vec![("Straße", 1.0)]);
+}
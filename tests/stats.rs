@@ -0,0 +1,133 @@
+extern crate commcomm;
+extern crate tempdir;
+
+use commcomm::arduino::Event;
+use commcomm::config::Configuration;
+use commcomm::decoder::InputEvent;
+use commcomm::stats::{self, Session, Statistics};
+
+use tempdir::TempDir;
+
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+fn session(started: &str, active_seconds: f64, characters: u64, words: u64) -> Session {
+    Session {
+        started: started.to_string(),
+        active_seconds: active_seconds,
+        flexes: characters * 2,
+        events: 20,
+        illegal: 2,
+        deletions: 5,
+        characters: characters,
+        words: words
+    }
+}
+
+#[test]
+fn metrics() {
+    let session = session("2017-01-01T12:00:00+01:00", 120.0, 60, 12);
+    assert_eq!(session.characters_per_minute(), 30.0);
+    assert_eq!(session.words_per_minute(), 6.0);
+    assert_eq!(session.flexes_per_character(), 2.0);
+    assert_eq!(session.illegal_rate(), 0.1);
+    assert_eq!(session.deletion_rate(), 0.25);
+    assert_eq!(session.seconds_per_word(), 10.0);
+
+    let empty = Session::default();
+    assert_eq!(empty.characters_per_minute(), 0.0);
+    assert_eq!(empty.seconds_per_word(), 0.0);
+}
+
+#[test]
+fn export() {
+    let temp_dir = TempDir::new("commcomm-test").unwrap();
+    for (name, json) in vec![
+        ("20170101-120000.json", r#"{"started": "a", "active_seconds": 60.0, "flexes": 40, "events": 20,
+                                     "illegal": 1, "deletions": 2, "characters": 20, "words": 4}"#),
+        ("20170102-120000.json", r#"{"started": "b", "active_seconds": 60.0, "flexes": 20, "events": 10,
+                                     "illegal": 0, "deletions": 1, "characters": 10, "words": 2}"#),
+        ("notes.txt", "not a session")
+    ] {
+        File::create(temp_dir.path().join(name)).and_then(|mut file| file.write_all(json.as_bytes())).unwrap();
+    }
+
+    let sessions = stats::load_sessions(temp_dir.path()).unwrap();
+    assert_eq!(sessions.iter().map(|session| &session.started[..]).collect::<Vec<_>>(), ["a", "b"]);
+
+    let total = Session::total(&sessions);
+    assert_eq!(total.characters, 30);
+    assert_eq!(total.words_per_minute(), 3.0);
+
+    let mut csv = Vec::new();
+    stats::write_csv(&sessions, &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("started,active_seconds,"));
+    assert!(lines[1].starts_with("a,60.0,40,20,1,2,20,4,20.00,4.00,2.00,"));
+
+    let mut json = Vec::new();
+    stats::write_json(&sessions, &mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert!(json.contains("\"sessions\""));
+    assert!(json.contains("\"total\""));
+}
+
+#[test]
+fn recording() {
+    let temp_dir = TempDir::new("commcomm-test").unwrap();
+    let directory = temp_dir.path().join("statistics");
+    let config_path = temp_dir.path().join("config.toml");
+    let config = format!(r#"
+[speech]
+engine = "sapi"
+
+[arduino]
+board = "leonardo"
+port = "COM3"
+sensors = []
+
+[decoder]
+[decoder.prediction]
+suggestions = 3
+
+[statistics]
+directory = '{}'
+"#, directory.display());
+    File::create(&config_path).and_then(|mut file| file.write_all(config.as_bytes())).unwrap();
+    let mut statistics = Statistics::new(&Configuration::new(&config_path).unwrap());
+
+    let start = Instant::now();
+    let at = |seconds| start + Duration::from_secs(seconds);
+    statistics.record_event(&Event::SensorFlexed(0), at(0));
+    statistics.record_event(&Event::SensorExtended(0), at(1));
+    statistics.record_input_event(&InputEvent::Letters("Hi".to_string()), at(2));
+    // A pause of a minute counts as being idle.
+    statistics.record_event(&Event::SensorFlexed(1), at(62));
+    statistics.record_input_event(&InputEvent::Macro(vec![
+        InputEvent::Word("Hi".to_string()),
+        InputEvent::Letters("there".to_string()),
+        InputEvent::DeleteLetter
+    ]), at(63));
+    statistics.record_input_event(&InputEvent::Undo, at(64));
+    statistics.record_input_event(&InputEvent::Illegal, at(65));
+    statistics.record_input_event(&InputEvent::Phrase("I'm thirsty.".to_string()), at(66));
+    assert_eq!(statistics.session().characters, 0);
+    assert!(!directory.exists());
+    statistics.record_input_event(&InputEvent::Line("Hi there.".to_string()), at(67));
+
+    let session = statistics.session();
+    assert_eq!(session.active_seconds, 7.0);
+    assert_eq!(session.flexes, 2);
+    assert_eq!(session.events, 8);
+    assert_eq!(session.illegal, 1);
+    assert_eq!(session.deletions, 1);
+    assert_eq!(session.characters, 9);
+    assert_eq!(session.words, 2);
+
+    let sessions = stats::load_sessions(&directory).unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].characters, 9);
+}